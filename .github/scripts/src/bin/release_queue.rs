@@ -0,0 +1,520 @@
+//! Release queue inspection tool
+//!
+//! Used by: maintainers, locally against a repository checkout
+//! Purpose: Inspect and repair `.github/release_queue/*.json` without hand-editing JSON
+//!
+//! Usage: release-queue [--dir <path>] [--repo <path>] [--branch <beta|main>] <command>
+//!
+//! Commands:
+//! - list: show queued commits in release order
+//! - show <sha>: show one queued commit and its audit trail
+//! - remove <sha>: drop a commit from its queue
+//! - move <sha> --to <N>: move a commit to position N within its priority lane
+//! - drain --up-to <sha> [--version <tag>]: remove a commit and everything
+//!   queued before it, logging them as released in `tag`
+//! - validate: check the queue files for consistency, failing if problems are found
+//! - stats: summarize queue sizes, lanes, release readiness and throughput
+//! - summary <tag>: print the Markdown summary of a release from the history log
+//! - prune: drop commits that no longer exist, left the branch, or are already
+//!   covered by a release tag (checked against the clone at `--repo`, default `.`)
+//!
+//! SHAs may be abbreviated to any unique prefix of at least 7 characters.
+//! Without `--branch`, read-only commands cover every release branch and
+//! commands that take a SHA use whichever branch queues it. Read-only commands
+//! never create the queue directory or migrate a legacy `queue.json`.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use github_workflow_scripts::init;
+use github_workflow_scripts::release_queue::commits::CommitVerifier;
+use github_workflow_scripts::release_queue::history;
+use github_workflow_scripts::release_queue::{self, eta, QueueItem, ReleaseQueue, QUEUE_DIR, RELEASE_BRANCHES};
+use std::collections::BTreeMap;
+use std::env;
+use std::process::ExitCode;
+
+/// Shortest SHA prefix accepted on the command line
+const MIN_PREFIX_LEN: usize = 7;
+
+/// A parsed command line
+#[derive(Debug, PartialEq)]
+struct Cli {
+    dir: String,
+    repo: String,
+    branch: Option<String>,
+    command: Command,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    List,
+    Show { sha: String },
+    Remove { sha: String },
+    Move { sha: String, to: usize },
+    Drain { up_to: String, version: Option<String> },
+    Summary { version: String },
+    Validate,
+    Stats,
+    Prune,
+    Help,
+}
+
+impl Command {
+    /// Whether the command only inspects the queue
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Command::List | Command::Show { .. } | Command::Summary { .. } | Command::Validate | Command::Stats
+        )
+    }
+}
+
+/// Parse the arguments following the program name
+fn parse_args(args: &[String]) -> Result<Cli> {
+    let mut dir = QUEUE_DIR.to_string();
+    let mut repo = ".".to_string();
+    let mut branch = None;
+    let mut to = None;
+    let mut up_to = None;
+    let mut version = None;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| iter.next().cloned().with_context(|| format!("{} requires a value", flag));
+        match arg.as_str() {
+            "--dir" => dir = value("--dir")?,
+            "--repo" => repo = value("--repo")?,
+            "--branch" => branch = Some(value("--branch")?),
+            "--to" => {
+                let position = value("--to")?;
+                to = Some(position.parse::<usize>().with_context(|| format!("Invalid position: {}", position))?);
+            },
+            "--up-to" => up_to = Some(value("--up-to")?),
+            "--version" => version = Some(value("--version")?),
+            "-h" | "--help" => positional.insert(0, "help".to_string()),
+            flag if flag.starts_with("--") => bail!("Unknown option: {}", flag),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let name = positional.next().unwrap_or_else(|| "help".to_string());
+    let mut sha = || positional.next().with_context(|| format!("{} requires a commit SHA", name));
+
+    let command = match name.as_str() {
+        "list" => Command::List,
+        "show" => Command::Show { sha: sha()? },
+        "remove" => Command::Remove { sha: sha()? },
+        "move" => Command::Move {
+            sha: sha()?,
+            to: to.context("move requires --to <position>")?,
+        },
+        "drain" => Command::Drain { up_to: up_to.context("drain requires --up-to <sha>")?, version },
+        "summary" => Command::Summary {
+            version: positional.next().context("summary requires a version tag")?,
+        },
+        "validate" => Command::Validate,
+        "stats" => Command::Stats,
+        "prune" => Command::Prune,
+        "help" => Command::Help,
+        other => bail!("Unknown command: {}\n\n{}", other, usage()),
+    };
+
+    if let Some(branch) = &branch {
+        if !release_queue::is_release_branch(branch) {
+            bail!("Invalid branch '{}': expected one of {}", branch, RELEASE_BRANCHES.join("/"));
+        }
+    }
+
+    Ok(Cli { dir, repo, branch, command })
+}
+
+fn usage() -> String {
+    [
+        "Usage: release-queue [--dir <path>] [--repo <path>] [--branch <beta|main>] <command>",
+        "",
+        "Commands:",
+        "  list                    Show queued commits in release order",
+        "  show <sha>              Show one queued commit and its audit trail",
+        "  remove <sha>            Drop a commit from its queue",
+        "  move <sha> --to <N>     Move a commit to position N within its lane",
+        "  drain --up-to <sha>     Remove a commit and everything before it",
+        "    [--version <tag>]     Record the drained commits as released in <tag>",
+        "  summary <tag>           Print the Markdown summary of a release",
+        "  validate                Check queue files for consistency",
+        "  stats                   Summarize queue sizes, readiness and throughput",
+        "  prune                   Drop unreachable and already released commits",
+    ]
+    .join("\n")
+}
+
+/// Runs commands against the queue directory
+struct QueueTool {
+    queue: ReleaseQueue,
+    verifier: CommitVerifier,
+    branch: Option<String>,
+}
+
+impl QueueTool {
+    /// Branches a command applies to
+    fn branches(&self) -> Vec<&str> {
+        match &self.branch {
+            Some(branch) => vec![branch.as_str()],
+            None => RELEASE_BRANCHES.to_vec(),
+        }
+    }
+
+    /// Find the single queued commit matching a SHA or unique prefix
+    fn resolve(&self, sha: &str) -> Result<(String, QueueItem)> {
+        if sha.len() < MIN_PREFIX_LEN {
+            bail!("SHA '{}' is too short; use at least {} characters", sha, MIN_PREFIX_LEN);
+        }
+
+        let mut matches = Vec::new();
+        for branch in self.branches() {
+            for item in self.queue.load(branch)?.items {
+                if item.commit.starts_with(sha) {
+                    matches.push((branch.to_string(), item));
+                }
+            }
+        }
+
+        match matches.len() {
+            0 => bail!("No queued commit matches {}", sha),
+            1 => Ok(matches.remove(0)),
+            _ => {
+                let found: Vec<String> = matches
+                    .iter()
+                    .map(|(branch, item)| format!("{} ({})", item.commit, branch))
+                    .collect();
+                bail!("{} is ambiguous; it matches {}", sha, found.join(", "))
+            }
+        }
+    }
+
+    fn list(&self) -> Result<()> {
+        for branch in self.branches() {
+            let queue = self.queue.load(branch)?;
+            println!("{} ({} queued)", branch, queue.items.len());
+
+            for (index, item) in queue.items.iter().enumerate() {
+                println!(
+                    "  {:>3}  {}  {:<6}  {:<9}  {}  {}",
+                    index + 1,
+                    item.commit.chars().take(12).collect::<String>(),
+                    item.priority,
+                    item.state,
+                    item.date.format("%Y-%m-%d %H:%M"),
+                    item.pr.map(|pr| format!("#{}", pr)).unwrap_or_default()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn show(&self, sha: &str) -> Result<()> {
+        let (branch, item) = self.resolve(sha)?;
+        let queue = self.queue.load(&branch)?;
+        let position = queue.position(&item.commit).unwrap_or_default();
+
+        println!("Commit:    {}", item.commit);
+        println!("Branch:    {}", branch);
+        println!("Position:  {} of {}", position, queue.items.len());
+        println!("Priority:  {}", item.priority);
+        println!("State:     {}", item.state);
+        if let Some(reason) = item.failure_reason() {
+            println!("Failure:   {}", reason);
+        }
+        if item.retries > 0 {
+            println!("Retries:   {}", item.retries);
+        }
+        println!("Queued:    {}", item.date.to_rfc3339());
+        if let Some(pr) = item.pr {
+            println!("PR:        #{}", pr);
+        }
+        if let Some(started_at) = item.started_at {
+            println!("Started:   {}", started_at.to_rfc3339());
+        }
+        println!("Estimate:  {}", self.queue.estimate(&branch, position)?.describe());
+
+        if !item.transitions.is_empty() {
+            println!("History:");
+            for transition in &item.transitions {
+                let reason = transition.reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default();
+                println!("  {}  {} -> {}{}", transition.at.to_rfc3339(), transition.from, transition.to, reason);
+            }
+        }
+
+        let audit: Vec<_> = queue.audit.iter().filter(|entry| entry.commit == item.commit).collect();
+        if !audit.is_empty() {
+            println!("Audit:");
+            for entry in audit {
+                let from = entry.from.map(|p| p.to_string()).unwrap_or_else(|| "new".to_string());
+                println!("  {}  {} -> {} by {}: {}", entry.at.to_rfc3339(), from, entry.to, entry.actor, entry.reason);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, sha: &str) -> Result<()> {
+        let (branch, item) = self.resolve(sha)?;
+        self.queue.remove(&branch, &item.commit)?;
+        println!("Removed {} from the {} queue", item.commit, branch);
+        Ok(())
+    }
+
+    fn move_to(&self, sha: &str, to: usize) -> Result<()> {
+        let (branch, item) = self.resolve(sha)?;
+        let position = self.queue.move_to(&branch, &item.commit, to)?;
+        println!("Moved {} to position {} in the {} queue", item.commit, position, branch);
+        Ok(())
+    }
+
+    fn drain(&self, sha: &str, version: Option<&str>) -> Result<()> {
+        let (branch, item) = self.resolve(sha)?;
+        let drained = self.queue.drain_through(&branch, &item.commit, version)?;
+        println!("Drained {} commit(s) from the {} queue:", drained.len(), branch);
+        for item in drained {
+            println!("  {}", item.commit);
+        }
+        Ok(())
+    }
+
+    fn summary(&self, version: &str) -> Result<()> {
+        let entries = history::read(self.queue.dir())?;
+        let summary = history::render_summary(&entries, version)
+            .with_context(|| format!("No released commits recorded for {}", version))?;
+        print!("{}", summary);
+        Ok(())
+    }
+
+    /// Returns the number of problems found
+    fn validate(&self) -> Result<usize> {
+        let mut total = 0;
+        if let Err(err) = self.queue.policy() {
+            println!("policy: {:#}", err);
+            total += 1;
+        }
+
+        for branch in self.branches() {
+            let problems = match self.queue.load(branch) {
+                Ok(queue) => queue.problems(),
+                Err(err) => vec![format!("{:#}", err)],
+            };
+
+            if problems.is_empty() {
+                println!("{}: ok", branch);
+            }
+            for problem in &problems {
+                println!("{}: {}", branch, problem);
+            }
+            total += problems.len();
+        }
+        Ok(total)
+    }
+
+    fn prune(&self) -> Result<()> {
+        for branch in self.branches() {
+            let pruned = self.queue.prune(branch, &self.verifier)?;
+            println!("{}: pruned {} commit(s)", branch, pruned.len());
+            for (item, reason) in pruned {
+                println!("  {}  {}", item.commit, reason);
+            }
+        }
+        Ok(())
+    }
+
+    fn stats(&self) -> Result<()> {
+        let policy = self.queue.policy()?;
+
+        for branch in self.branches() {
+            let queue = self.queue.load(branch)?;
+            let readiness = policy.for_branch(branch).evaluate(&queue, Utc::now());
+
+            let mut lanes = BTreeMap::new();
+            for item in &queue.items {
+                *lanes.entry(item.priority).or_insert(0) += 1;
+            }
+            let lanes: Vec<String> = lanes.iter().map(|(lane, count)| format!("{} {}", count, lane)).collect();
+
+            println!("{}", branch);
+            println!("  Queued:        {} ({})", queue.items.len(), if lanes.is_empty() { "empty".to_string() } else { lanes.join(", ") });
+            if let Some(oldest) = queue.items.iter().map(|item| item.date).min() {
+                let age = Utc::now() - oldest;
+                if age.num_days() > 0 {
+                    println!("  Oldest:        {} day(s) ago", age.num_days());
+                } else {
+                    println!("  Oldest:        {} ago", eta::format_duration(age.to_std().unwrap_or_default()));
+                }
+            }
+            if let Some(last) = queue.last_released_at {
+                println!("  Last release:  {}", last.to_rfc3339());
+            }
+            if readiness.ready {
+                println!("  Ready:         yes ({})", readiness.reasons.join("; "));
+            } else {
+                println!("  Ready:         no ({} more commit(s) needed)", readiness.remaining_items);
+            }
+
+            // The estimate for the second position is one median release
+            let estimate = queue.estimate(2, 1);
+            if estimate.samples > 0 {
+                println!(
+                    "  Release time:  median {} over the last {} release(s)",
+                    eta::format_duration(estimate.expected),
+                    estimate.samples
+                );
+            }
+            println!("  Audit entries: {}", queue.audit.len());
+        }
+        Ok(())
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode> {
+    if cli.command == Command::Help {
+        println!("{}", usage());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Inspecting a checkout must not create the directory or migrate legacy files
+    let queue = if cli.command.is_read_only() {
+        ReleaseQueue::open_read_only(&cli.dir)?
+    } else {
+        ReleaseQueue::open(&cli.dir)?
+    };
+    let tool = QueueTool {
+        queue,
+        verifier: CommitVerifier::new(&cli.repo),
+        branch: cli.branch,
+    };
+
+    match cli.command {
+        Command::List => tool.list()?,
+        Command::Show { sha } => tool.show(&sha)?,
+        Command::Remove { sha } => tool.remove(&sha)?,
+        Command::Move { sha, to } => tool.move_to(&sha, to)?,
+        Command::Drain { up_to, version } => tool.drain(&up_to, version.as_deref())?,
+        Command::Summary { version } => tool.summary(&version)?,
+        Command::Validate => {
+            let problems = tool.validate()?;
+            if problems > 0 {
+                eprintln!("Found {} problem(s)", problems);
+                return Ok(ExitCode::FAILURE);
+            }
+        },
+        Command::Stats => tool.stats()?,
+        Command::Prune => tool.prune()?,
+        Command::Help => unreachable!("help is printed before the queue is opened"),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    run(parse_args(&args)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use github_workflow_scripts::release_queue::history::HistoryEvent;
+    use std::fs;
+    use std::path::Path;
+
+    const SHA_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const SHA_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    const SHA_C: &str = "cccccccccccccccccccccccccccccccccccccccc";
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    /// A tool over a temporary queue with `shas` queued on beta in order
+    fn tool_with(dir: &Path, shas: &[&str]) -> QueueTool {
+        let queue = ReleaseQueue::open(dir).unwrap();
+        for sha in shas {
+            queue.add("beta", sha, None).unwrap();
+        }
+        QueueTool { queue, verifier: CommitVerifier::new("."), branch: None }
+    }
+
+    fn queued(tool: &QueueTool) -> Vec<String> {
+        tool.queue.load("beta").unwrap().items.into_iter().map(|item| item.commit).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cli = parse_args(&args("--branch main move abc1234 --to 3")).unwrap();
+        assert_eq!(cli.branch.as_deref(), Some("main"));
+        assert_eq!(cli.dir, QUEUE_DIR);
+        assert_eq!(cli.command, Command::Move { sha: "abc1234".to_string(), to: 3 });
+
+        assert_eq!(parse_args(&args("drain --up-to abc1234")).unwrap().command,
+            Command::Drain { up_to: "abc1234".to_string(), version: None });
+        assert_eq!(parse_args(&args("summary beta-v1.0.0")).unwrap().command,
+            Command::Summary { version: "beta-v1.0.0".to_string() });
+        assert!(parse_args(&args("move abc1234")).is_err());
+        assert!(parse_args(&args("--branch develop list")).is_err());
+        assert!(parse_args(&args("frobnicate")).is_err());
+    }
+
+    #[test]
+    fn test_remove_resolves_prefixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = tool_with(dir.path(), &[SHA_A, SHA_B]);
+
+        assert!(tool.remove("aaaaaa").is_err());
+        assert!(tool.remove("ddddddd").is_err());
+        tool.remove("aaaaaaa").unwrap();
+        assert_eq!(queued(&tool), vec![SHA_B]);
+
+        let history = history::read(tool.queue.dir()).unwrap();
+        assert_eq!(history[0].event, HistoryEvent::Removed);
+    }
+
+    #[test]
+    fn test_move_to_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = tool_with(dir.path(), &[SHA_A, SHA_B, SHA_C]);
+
+        tool.move_to(&SHA_C[..8], 1).unwrap();
+        assert_eq!(queued(&tool), vec![SHA_C, SHA_A, SHA_B]);
+        assert!(tool.move_to(SHA_A, 0).is_err());
+    }
+
+    #[test]
+    fn test_drain_up_to_records_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = tool_with(dir.path(), &[SHA_A, SHA_B, SHA_C]);
+
+        tool.drain(&SHA_B[..7], Some("beta-v1.0.0")).unwrap();
+        assert_eq!(queued(&tool), vec![SHA_C]);
+
+        let history = history::read(tool.queue.dir()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|entry| entry.event == HistoryEvent::Released
+            && entry.version.as_deref() == Some("beta-v1.0.0")));
+    }
+
+    #[test]
+    fn test_validate_counts_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = tool_with(dir.path(), &[SHA_A]);
+        assert_eq!(tool.validate().unwrap(), 0);
+
+        fs::write(tool.queue.path_for("main"), "not json").unwrap();
+        assert_eq!(tool.validate().unwrap(), 1);
+
+        // Read-only commands never create the queue directory
+        let missing = dir.path().join("missing");
+        let cli = parse_args(&args(&format!("--dir {} validate", missing.display()))).unwrap();
+        assert!(run(cli).is_err());
+        assert!(!missing.exists());
+    }
+}
@@ -1,11 +1,13 @@
 use anyhow::{Context, Result};
-use github_workflow_scripts::{get_logger, init};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::{get_logger, github, init};
 use std::{env, process::Command};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init();
     let logger = get_logger(false);
+    let dry_run = DryRun::from_env("build_image");
 
     // Get environment variables
     let tag = env::var("INPUT_TAG").context("INPUT_TAG not set")?;
@@ -24,15 +26,22 @@ async fn main() -> Result<()> {
 
     // Store formatted strings to extend their lifetime
     let stable_tag = format!("{}:stable", base_image);
+    // Immutable tag of this release, which rollback deletes
+    let release_tag = format!("{}:{}", base_image, tag);
+    let tag_release = release_tag != version_tag;
 
     // Prepare build context
-    std::fs::create_dir_all("distributions/dockerhub")
-        .context("Failed to create build directory")?;
+    if !dry_run.is_enabled() {
+        std::fs::create_dir_all("distributions/dockerhub")
+            .context("Failed to create build directory")?;
+    }
     
-    Command::new("cp")
-        .args(["-r", "startup", "distributions/dockerhub/"])
-        .status()
-        .context("Failed to copy build context")?;
+    let mut copy = Command::new("cp");
+    copy.args(["-r", "startup", "distributions/dockerhub/"]);
+    if !dry_run.intercept_command("Copy build context", &copy)? {
+        copy.status()
+            .context("Failed to copy build context")?;
+    }
 
     // Build image with appropriate tags
     let mut build_args = vec![
@@ -45,33 +54,69 @@ async fn main() -> Result<()> {
     if environment == "main" {
         build_args.extend(&["-t", &stable_tag]);
     }
+    if tag_release {
+        build_args.extend(&["-t", &release_tag]);
+    }
 
-    Command::new("docker")
-        .args(&build_args)
-        .current_dir("distributions/dockerhub")
-        .status()
-        .context("Failed to build image")?;
+    let mut build = Command::new("docker");
+    build.args(&build_args).current_dir("distributions/dockerhub");
+    if !dry_run.intercept_command("Build image", &build)? {
+        build.status()
+            .context("Failed to build image")?;
+    }
 
     // Push images for protected branches
     if ["main", "beta", "develop"].contains(&environment.as_str()) {
         logger.info("Pushing image to DockerHub");
-        Command::new("docker")
-            .args(["push", &version_tag])
-            .status()
-            .context("Failed to push image")?;
+        let mut push = Command::new("docker");
+        push.args(["push", &version_tag]);
+        if !dry_run.intercept_command("Push image", &push)? {
+            push.status()
+                .context("Failed to push image")?;
+        }
 
         if environment == "main" {
-            Command::new("docker")
-                .args(["push", &stable_tag])
-                .status()
-                .context("Failed to push stable tag")?;
+            let mut push_stable = Command::new("docker");
+            push_stable.args(["push", &stable_tag]);
+            if !dry_run.intercept_command("Push stable tag", &push_stable)? {
+                push_stable.status()
+                    .context("Failed to push stable tag")?;
+            }
+        }
+
+        if tag_release {
+            let mut push_release = Command::new("docker");
+            push_release.args(["push", &release_tag]);
+            if !dry_run.intercept_command("Push release tag", &push_release)? {
+                push_release.status()
+                    .context("Failed to push release tag")?;
+            }
+        }
+
+        // Record the pushed image's digest so the release manifest can pin it
+        if !dry_run.is_enabled() {
+            let output = Command::new("docker")
+                .args(["image", "inspect", "--format", "{{index .RepoDigests 0}}", &version_tag])
+                .output()
+                .context("Failed to inspect image")?;
+            let repo_digest = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            match repo_digest.split_once('@') {
+                Some((_, digest)) if output.status.success() => {
+                    logger.info(&format!("Image digest: {}", digest));
+                    github::set_output("image_digest", digest);
+                },
+                _ => logger.warn(&format!("No registry digest found for {}", version_tag)),
+            }
         }
     }
 
     // Cleanup
-    std::fs::remove_dir_all("distributions/dockerhub/startup")
-        .context("Failed to cleanup build context")?;
+    let build_context = PlannedAction::RemovePath { path: "distributions/dockerhub/startup".to_string() };
+    if !dry_run.intercept("Clean up build context", build_context)? {
+        std::fs::remove_dir_all("distributions/dockerhub/startup")
+            .context("Failed to cleanup build context")?;
+    }
 
-    println!("::set-output name=image_tag::{}", version_tag);
+    github::set_output("image_tag", &version_tag);
     Ok(())
 }
@@ -7,11 +7,11 @@ use github_workflow_scripts::{Logger, init, get_logger};
 struct ArtifactCheck<'a> {
     docker: bool,
     direct_download: bool,  // Renamed from bittorrent
-    logger: &'a Box<dyn Logger>,
+    logger: &'a dyn Logger,
 }
 
 impl<'a> ArtifactCheck<'a> {
-    fn new(logger: &'a Box<dyn Logger>) -> Self {
+    fn new(logger: &'a dyn Logger) -> Self {
         Self {
             docker: false,
             direct_download: false,  // Renamed from bittorrent
@@ -101,7 +101,7 @@ async fn main() -> Result<()> {
         logger.debug("Local environment detected - skipping GitHub API calls");
         logger.info("✅ Script compiled successfully!");
         
-        let mut checker = ArtifactCheck::new(&logger);
+        let mut checker = ArtifactCheck::new(logger.as_ref());
         checker.check_artifacts(
             "example-owner",
             "example-repo",
@@ -134,7 +134,7 @@ async fn main() -> Result<()> {
     logger.debug(&format!("Head SHA: {}", head_sha));
     
     logger.info("📦 Checking for artifacts...");
-    let mut checker = ArtifactCheck::new(&logger);
+    let mut checker = ArtifactCheck::new(logger.as_ref());
     checker.check_artifacts(
         owner,
         repo,
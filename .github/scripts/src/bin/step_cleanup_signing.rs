@@ -0,0 +1,33 @@
+//! Signing cleanup script for setup-git-signing action
+//! Used by: ./.github/actions/cleanup-git-signing/action.yml
+//! Purpose: Stops the gpg-agent of the job's temporary GNUPGHOME, deletes it
+//! and removes the signing settings from the workspace repository's git config
+
+use anyhow::Result;
+use github_workflow_scripts::{get_logger, init};
+use github_workflow_scripts::signing::{self, GitScope, Gpg};
+use std::env;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init();
+    let logger = get_logger(false);
+    logger.info("🧹 Cleaning up git signing...");
+
+    // Without a GNUPGHOME from the setup step there is no keyring of ours
+    match env::var_os("GNUPGHOME").filter(|home| !home.is_empty()) {
+        Some(home) => {
+            let gpg = Gpg::new(home);
+            if signing::remove_keyring(&gpg)? {
+                logger.info(&format!("🗑️ Removed keyring {}", gpg.home().display()));
+            } else {
+                logger.info(&format!("Keyring {} is not temporary, left in place", gpg.home().display()));
+            }
+        },
+        None => logger.info("No GNUPGHOME set, no keyring to remove"),
+    }
+
+    signing::reset_git(&GitScope::workspace())?;
+    logger.info("✅ Git signing cleaned up");
+    Ok(())
+}
@@ -1,10 +1,12 @@
 //! Git configuration for create-release action
 //! Used by: ./.github/actions/create-release/action.yml
-//! Purpose: Sets up git config for bot commits
+//! Purpose: Sets up git config for bot commits, signed with the method
+//! selected by INPUT_SIGNING_METHOD (gpg, ssh or x509)
 
 use anyhow::{Context, Result};
-use github_workflow_scripts::{get_logger, init};
-use std::{env, process::Command};
+use github_workflow_scripts::{get_logger, init, github};
+use github_workflow_scripts::signing::{self, configure_git, GitScope, Identity, SignerConfig, SigningMethod};
+use std::env;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,27 +18,27 @@ async fn main() -> Result<()> {
     // Get required inputs
     let bot_name = env::var("INPUT_BOT_NAME").context("BOT_NAME not set")?;
     let bot_email = env::var("INPUT_BOT_EMAIL").context("BOT_EMAIL not set")?;
-    let gpg_key = env::var("INPUT_BOT_GPG_PRIVATE_KEY").context("BOT_GPG_PRIVATE_KEY not set")?;
-
-    // Configure git
-    let configs = [
-        ("user.name", bot_name),
-        ("user.email", bot_email),
-        ("user.signingkey", gpg_key),
-        ("commit.gpgsign", "true".to_string()),
-    ];
-
-    for (key, value) in configs {
-        let status = Command::new("git")
-            .args(["config", "--global", key, &value])
-            .status()
-            .context(format!("Failed to set git config {}", key))?;
-
-        if !status.success() {
-            anyhow::bail!("Failed to configure git {}", key);
-        }
-    }
-
-    logger.info("✅ Git configuration completed");
+    let method: SigningMethod = env::var("INPUT_SIGNING_METHOD").unwrap_or_default().parse()?;
+    let key = match method {
+        SigningMethod::Gpg => Some(env::var("INPUT_BOT_GPG_PRIVATE_KEY").context("BOT_GPG_PRIVATE_KEY not set")?),
+        SigningMethod::Ssh => Some(env::var("INPUT_BOT_SSH_SIGNING_KEY").context("BOT_SSH_SIGNING_KEY not set")?),
+        SigningMethod::X509 => None,
+    };
+    let identity = Identity::new(&bot_name, &bot_email);
+
+    let config = SignerConfig {
+        method,
+        key,
+        passphrase: env::var("INPUT_BOT_GPG_PASSPHRASE").ok(),
+        program: env::var("INPUT_X509_PROGRAM").ok(),
+        generate_fallback: false,
+    };
+    let (gpg, signer) = signing::setup(&config, &identity)?;
+    github::set_env("GNUPGHOME", &gpg.home().display().to_string());
+
+    // Only the workspace repository is configured, never ~/.gitconfig
+    configure_git(&GitScope::workspace(), &identity, Some(signer.as_ref()))?;
+
+    logger.info(&format!("✅ Git configuration completed, signing with {} {}", method, signer.key_id()));
     Ok(())
 }
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use github_workflow_scripts::{get_logger, init};
+use github_workflow_scripts::release_queue::ReleaseQueue;
 use octocrab::Octocrab;
 use std::env;
 
@@ -11,8 +12,6 @@ struct PrCreator {
     input_branch: String,
     sha: String,
     position: String,
-    remaining: String,
-    est_time: String,
     owner: String,
     repo: String,
 }
@@ -28,8 +27,6 @@ impl PrCreator {
         let input_branch = env::var("INPUT_BRANCH").context("Missing INPUT_BRANCH")?;
         let sha = env::var("INPUT_SHA").context("Missing INPUT_SHA")?;
         let position = env::var("QUEUE_POSITION").context("Missing QUEUE_POSITION")?;
-        let remaining = env::var("QUEUE_REMAINING").context("Missing QUEUE_REMAINING")?;
-        let est_time = env::var("QUEUE_ESTIMATED_TIME").context("Missing QUEUE_ESTIMATED_TIME")?;
 
         // Extract owner and repo from GITHUB_REPOSITORY
         let github_repo = env::var("GITHUB_REPOSITORY").context("Missing GITHUB_REPOSITORY")?;
@@ -47,8 +44,6 @@ impl PrCreator {
             input_branch,
             sha,
             position,
-            remaining,
-            est_time,
             owner,
             repo,
         })
@@ -264,9 +259,10 @@ impl PrCreator {
         
         // Step 4: Try multiple strategies until one works
         let title = format!("📦 Queue Update: Release {} (Position: {})", self.sha, self.position);
+        let report = ReleaseQueue::open_default()?.report(&base_branch)?.with_highlight(&self.sha);
         let body = format!(
-            "This PR updates the release queue for commit {}.\n\nQueue Status:\n- Position: {}\n- Items needed: {} more\n- Estimated time: {}",
-            self.sha, self.position, self.remaining, self.est_time
+            "This PR updates the release queue for commit {}.\n\n{}",
+            self.sha, report.to_comment()
         );
         
         // Try each branch format strategy
@@ -4,7 +4,14 @@
 
 use anyhow::{Context, Result, anyhow};
 use github_workflow_scripts::{get_logger, init, github, Logger};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::release::manifest::{ImageInfo, ReleaseManifest, MANIFEST_FILE};
+use github_workflow_scripts::release::notes::{self, Promotion, ReleaseNotes};
+use github_workflow_scripts::release::{NewRelease, ReleaseClient};
+use github_workflow_scripts::version::Version;
+use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
 use std::process::Command;
 use std::fs;
 
@@ -14,6 +21,7 @@ async fn main() -> Result<()> {
     // Initialize logging and configuration
     init();
     let logger = get_logger(false);
+    let dry_run = DryRun::from_env("create_release");
     
     logger.info("🚀 Creating GitHub release...");
     
@@ -43,32 +51,64 @@ async fn main() -> Result<()> {
     
     let allow_unsigned = env::var("INPUT_ALLOW_UNSIGNED")
         .map(|v| v == "true")
-        .unwrap_or(true);
+        .unwrap_or(false);
+    
+    // Files to attach to the release; missing ones are skipped
+    let assets = env::var("INPUT_ASSETS")
+        .unwrap_or_else(|_| "dev-environment.tar.gz checksum.txt".to_string())
+        .split_whitespace()
+        .map(PathBuf::from)
+        .collect();
+    
+    // Docker image published with the release, recorded in the manifest
+    let image = match (env::var("INPUT_IMAGE"), env::var("INPUT_IMAGE_DIGEST")) {
+        (Ok(reference), Ok(digest)) if !reference.is_empty() && !digest.is_empty() => {
+            Some(ImageInfo { reference, digest })
+        },
+        _ => None,
+    };
+    
+    // Image tags listed in the release notes, defaulting to the manifest image
+    let image_tags = match env::var("INPUT_IMAGE_TAGS") {
+        Ok(tags) if !tags.trim().is_empty() => tags.split_whitespace().map(String::from).collect(),
+        _ => image.iter().map(|image| image.reference.clone()).collect(),
+    };
+    
+    // Beta tag this release was promoted from, recorded in the notes
+    let promoted_from = env::var("INPUT_PROMOTED_FROM").ok().filter(|tag| !tag.trim().is_empty());
     
     // No need to normalize for our custom version format - use as-is
     logger.info(&format!("Using version: {}", version));
     
-    // Set environment variables for GitHub CLI
-    env::set_var("GITHUB_TOKEN", &github_token);
     let github_repository = env::var("GITHUB_REPOSITORY")
         .context("Missing GITHUB_REPOSITORY environment variable")?;
+    let client = ReleaseClient::new(&github_token, &github_repository)?;
     
     // Process flow: Check tag → Create tag → Push tag → Create release
     if !tag_exists(&version, logger.as_ref())? {
         // Try to create and push tag
-        create_and_push_tag(&version, &release_sha, allow_unsigned, logger.as_ref())?;
+        create_and_push_tag(&version, &release_sha, allow_unsigned, &dry_run, logger.as_ref())?;
     }
     
     // Create GitHub release
+    let options = ReleaseOptions {
+        prerelease: prerelease || is_beta_version(&version),
+        draft,
+        generate_notes,
+        assets,
+        image,
+        image_tags,
+        repository: github_repository,
+        promoted_from,
+    };
     create_github_release(
         &version, 
         &release_sha, 
-        prerelease || is_beta_version(&version), 
-        draft, 
-        generate_notes,
-        &github_repository,
+        &options,
+        &client,
+        &dry_run,
         logger.as_ref()
-    )?;
+    ).await?;
     
     Ok(())
 }
@@ -79,7 +119,7 @@ fn tag_exists(version: &str, logger: &dyn Logger) -> Result<bool> {
     
     // Fetch latest tags to ensure we see the new one
     let fetch_result = Command::new("git")
-        .args(&["fetch", "--tags"])
+        .args(["fetch", "--tags"])
         .output();
     
     if let Err(e) = fetch_result {
@@ -88,7 +128,7 @@ fn tag_exists(version: &str, logger: &dyn Logger) -> Result<bool> {
     
     // Verify our tag exists
     let tag_check = Command::new("git")
-        .args(&["tag", "-l", version])
+        .args(["tag", "-l", version])
         .output()
         .context("Failed to check if tag exists")?;
     
@@ -105,17 +145,22 @@ fn tag_exists(version: &str, logger: &dyn Logger) -> Result<bool> {
     Ok(tag_exists)
 }
 
-/// Determine if a version is a beta release based on its prefix
+/// Determine if a version is a beta release from its channel prefix or pre-release identifiers
 fn is_beta_version(version: &str) -> bool {
-    version.starts_with("beta-")
+    version.parse::<Version>().map(|version| version.is_beta()).unwrap_or(false)
 }
 
 /// Create and push a tag to the remote repository
-fn create_and_push_tag(version: &str, commit_sha: &str, allow_unsigned: bool, logger: &dyn Logger) -> Result<()> {
-    // Configure GPG for batch mode operation
-    configure_gpg_for_batch_mode(logger)?;
+fn create_and_push_tag(version: &str, commit_sha: &str, allow_unsigned: bool, dry_run: &DryRun, logger: &dyn Logger) -> Result<()> {
+    // A dry run plans the signed tag and push without touching the GPG setup
+    if dry_run.is_enabled() {
+        dry_run.intercept_command("Create signed tag", &signed_tag_command(version, commit_sha))?;
+        dry_run.intercept_command("Push tag", &push_tag_command(version))?;
+        return Ok(());
+    }
     
-    // First, try to create a signed tag
+    // First, try to create a signed tag with the keyring setup-git-signing
+    // exported as GNUPGHOME
     logger.info("Creating signed tag...");
     let signing_result = create_signed_tag(version, commit_sha);
     
@@ -126,10 +171,7 @@ fn create_and_push_tag(version: &str, commit_sha: &str, allow_unsigned: bool, lo
         if allow_unsigned {
             logger.info("Falling back to unsigned tag...");
             
-            // Properly disable GPG signing for this operation
-            disable_git_signing(logger)?;
-            
-            // Create truly unsigned tag
+            // Create truly unsigned tag, leaving the signing config in place
             let unsigned_result = create_unsigned_tag(version, commit_sha);
             if let Err(e) = unsigned_result {
                 return Err(anyhow!("Failed to create unsigned tag: {}", e));
@@ -141,8 +183,7 @@ fn create_and_push_tag(version: &str, commit_sha: &str, allow_unsigned: bool, lo
     
     // Push the tag to remote
     logger.info("Pushing tag to remote...");
-    let push_result = Command::new("git")
-        .args(&["push", "origin", &format!("refs/tags/{}", version)])
+    let push_result = push_tag_command(version)
         .output()
         .context("Failed to push tag")?;
         
@@ -157,79 +198,8 @@ fn create_and_push_tag(version: &str, commit_sha: &str, allow_unsigned: bool, lo
     Ok(())
 }
 
-/// Configure GPG for batch mode operation
-fn configure_gpg_for_batch_mode(logger: &dyn Logger) -> Result<()> {
-    logger.info("Configuring GPG for batch mode operation");
-    
-    // Create necessary GPG configuration directories
-    let home_dir = env::var("HOME").unwrap_or_else(|_| "/home/runner".to_string());
-    let gpg_dir = format!("{}/.gnupg", home_dir);
-    fs::create_dir_all(&gpg_dir).context("Failed to create GPG directory")?;
-    
-    // Create gpg.conf with appropriate settings
-    let gpg_conf_path = format!("{}/gpg.conf", gpg_dir);
-    let gpg_conf_content = "allow-loopback-pinentry\npinentry-mode loopback\nno-tty\nbatch\n";
-    fs::write(&gpg_conf_path, gpg_conf_content)
-        .context("Failed to write GPG configuration")?;
-    
-    // Create gpg-agent.conf
-    let agent_conf_path = format!("{}/gpg-agent.conf", gpg_dir);
-    let agent_conf_content = "allow-loopback-pinentry\nallow-preset-passphrase\n";
-    fs::write(&agent_conf_path, agent_conf_content)
-        .context("Failed to write GPG agent configuration")?;
-    
-    // Set proper permissions
-    Command::new("chmod")
-        .args(&["700", &gpg_dir])
-        .output()
-        .ok();
-    
-    Command::new("chmod")
-        .args(&["600", &gpg_conf_path, &agent_conf_path])
-        .output()
-        .ok();
-    
-    // Set environment variables for GPG
-    env::set_var("GPG_TTY", "");
-    
-    // Reload GPG agent
-    Command::new("gpg-connect-agent")
-        .args(&["reloadagent", "/bye"])
-        .output()
-        .ok();
-    
-    logger.info("✅ GPG configured for batch mode");
-    Ok(())
-}
-
-/// Disable Git GPG signing temporarily
-fn disable_git_signing(logger: &dyn Logger) -> Result<()> {
-    logger.info("Temporarily disabling Git signing");
-    
-    // Unset signing key
-    Command::new("git")
-        .args(&["config", "--local", "--unset", "user.signingkey"])
-        .output()
-        .ok();
-    
-    // Disable commit signing
-    Command::new("git")
-        .args(&["config", "--local", "commit.gpgsign", "false"])
-        .output()
-        .context("Failed to disable commit signing")?;
-    
-    // Disable tag signing
-    Command::new("git")
-        .args(&["config", "--local", "tag.gpgsign", "false"])
-        .output()
-        .context("Failed to disable tag signing")?;
-    
-    logger.info("✅ Git signing disabled");
-    Ok(())
-}
-
-/// Create a signed Git tag
-fn create_signed_tag(version: &str, commit_sha: &str) -> Result<()> {
+/// Command that creates a signed Git tag
+fn signed_tag_command(version: &str, commit_sha: &str) -> Command {
     // Create the tag message based on the version type
     let message = if is_beta_version(version) {
         format!("Beta Release {}", version)
@@ -237,8 +207,21 @@ fn create_signed_tag(version: &str, commit_sha: &str) -> Result<()> {
         format!("Stable Release {}", version)
     };
 
-    let output = Command::new("git")
-        .args(&["tag", "-s", version, commit_sha, "-m", &message])
+    let mut command = Command::new("git");
+    command.args(["tag", "-s", version, commit_sha, "-m", &message]);
+    command
+}
+
+/// Command that pushes a tag to the remote
+fn push_tag_command(version: &str) -> Command {
+    let mut command = Command::new("git");
+    command.args(["push", "origin", &format!("refs/tags/{}", version)]);
+    command
+}
+
+/// Create a signed Git tag
+fn create_signed_tag(version: &str, commit_sha: &str) -> Result<()> {
+    let output = signed_tag_command(version, commit_sha)
         .output()
         .context("Failed to execute git tag command")?;
     
@@ -262,7 +245,7 @@ fn create_unsigned_tag(version: &str, commit_sha: &str) -> Result<()> {
     };
 
     let output = Command::new("git")
-        .args(&["tag", "-a", version, commit_sha, "-m", &message])
+        .args(["-c", "tag.gpgsign=false", "tag", "-a", version, commit_sha, "-m", &message])
         .output()
         .context("Failed to execute git tag command")?;
     
@@ -276,21 +259,81 @@ fn create_unsigned_tag(version: &str, commit_sha: &str) -> Result<()> {
     Ok(())
 }
 
-/// Create GitHub release using the GitHub CLI
-fn create_github_release(
-    version: &str,
-    release_sha: &str,
+/// Settings for the created release
+struct ReleaseOptions {
     prerelease: bool,
     draft: bool,
     generate_notes: bool,
-    github_repository: &str,
+    assets: Vec<PathBuf>,
+    image: Option<ImageInfo>,
+    image_tags: Vec<String>,
+    repository: String,
+    promoted_from: Option<String>,
+}
+
+/// Write the release notes from the commits and pull requests since the previous release
+async fn release_notes(
+    version: &str,
+    release_sha: &str,
+    options: &ReleaseOptions,
+    assets: &[&PathBuf],
+    client: &ReleaseClient,
+    logger: &dyn Logger
+) -> Result<String> {
+    let tags = Command::new("git")
+        .args(["tag", "-l"])
+        .output()
+        .context("Failed to list tags")?;
+    let tags = String::from_utf8_lossy(&tags.stdout).to_string();
+    let previous = match version.parse::<Version>() {
+        Ok(current) => notes::previous_tag(tags.lines(), &current),
+        Err(e) => {
+            logger.warn(&format!("Cannot find the previous release of {}: {}", version, e));
+            None
+        },
+    };
+    logger.info(&format!("Writing release notes since {}", previous.unwrap_or("the start of the history")));
+    
+    let commits = notes::commits_between(previous, release_sha)?;
+    let mut pulls = HashMap::new();
+    for number in commits.iter().filter_map(|commit| notes::pr_number(&commit.message)) {
+        // Labels only refine the grouping, so a failed lookup falls back to the commit
+        match client.pull_request(number).await {
+            Ok(Some(pr)) => {
+                pulls.insert(number, pr);
+            },
+            Ok(None) => logger.warn(&format!("Pull request #{} not found", number)),
+            Err(e) => logger.warn(&format!("Failed to look up pull request #{}: {:#}", number, e)),
+        }
+    }
+    
+    let mut notes = ReleaseNotes::new(version, previous, &commits, &pulls);
+    notes.repository = Some(options.repository.clone());
+    notes.image_tags = options.image_tags.clone();
+    notes.promotion = options.promoted_from.as_ref().map(|from| Promotion {
+        from: from.clone(),
+        commit: release_sha.to_string(),
+        image_digest: options.image.as_ref().map(|image| image.digest.clone()),
+    });
+    if let Some(checksum) = assets.iter().find(|path| path.file_name().is_some_and(|name| name == "checksum.txt")) {
+        notes.checksum = Some(fs::read_to_string(checksum).context("Failed to read checksum file")?);
+    }
+    logger.info(&format!("Release notes list {} changes from {} contributors", notes.changes.len(), notes.contributors().len()));
+    
+    Ok(notes.to_markdown())
+}
+
+/// Create the GitHub release for the tag, or reuse an existing one, and attach the assets
+async fn create_github_release(
+    version: &str,
+    release_sha: &str,
+    options: &ReleaseOptions,
+    client: &ReleaseClient,
+    dry_run: &DryRun,
     logger: &dyn Logger
 ) -> Result<()> {
     logger.info("Creating GitHub release...");
     
-    // Build command arguments
-    let mut args = vec!["release", "create", version, "--target", release_sha];
-    
     // Create appropriate title based on version type
     let title = if is_beta_version(version) {
         format!("Beta Release {}", version)
@@ -298,45 +341,87 @@ fn create_github_release(
         format!("Stable Release {}", version)
     };
     
-    args.push("--title");
-    args.push(&title);
-    
-    // Add optional flags
-    if prerelease {
-        args.push("--prerelease");
+    if options.prerelease {
         logger.info("Creating as pre-release based on version format or input flag");
     }
     
-    if draft {
-        args.push("--draft");
+    let assets: Vec<&PathBuf> = options.assets.iter()
+        .filter(|path| {
+            let exists = path.is_file();
+            if !exists {
+                logger.warn(&format!("Asset {} not found, skipping", path.display()));
+            }
+            exists
+        })
+        .collect();
+    
+    let body = if options.generate_notes {
+        let body = release_notes(version, release_sha, options, &assets, client, logger).await?;
+        github::add_step_summary(&body);
+        Some(body)
+    } else {
+        None
+    };
+    let new_release = NewRelease {
+        tag_name: version.to_string(),
+        target_commitish: release_sha.to_string(),
+        name: title,
+        body,
+        draft: options.draft,
+        prerelease: options.prerelease,
+        generate_release_notes: false,
+    };
+    
+    // The manifest is written outside the checkout so it never ends up in a commit
+    let manifest_path = env::var_os("RUNNER_TEMP")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+        .join(MANIFEST_FILE);
+    
+    if dry_run.is_enabled() {
+        let create = PlannedAction::Api { method: "POST".to_string(), route: client.releases_route() };
+        dry_run.record("Create GitHub release", create)?;
+        for path in &assets {
+            let upload = PlannedAction::Api {
+                method: "POST".to_string(),
+                route: format!("{}/{{release_id}}/assets?name={}", client.releases_route(), path.file_name().unwrap_or_default().to_string_lossy()),
+            };
+            dry_run.record("Upload release asset", upload)?;
+        }
+        dry_run.record("Write release manifest", PlannedAction::WriteFile { path: manifest_path.display().to_string() })?;
+        let upload = PlannedAction::Api {
+            method: "POST".to_string(),
+            route: format!("{}/{{release_id}}/assets?name={}", client.releases_route(), MANIFEST_FILE),
+        };
+        dry_run.record("Upload release manifest", upload)?;
+        return Ok(());
     }
     
-    if generate_notes {
-        args.push("--generate-notes");
+    let (release, created) = client.create_or_get(&new_release).await?;
+    if created {
+        logger.info(&format!("✅ Release created successfully: {}", release.html_url));
+    } else {
+        logger.info(&format!("Release for {} already exists, reusing it: {}", version, release.html_url));
     }
     
-    // Execute release command
-    let release_result = Command::new("gh")
-        .args(&args)
-        .output()
-        .context("Failed to execute GitHub CLI")?;
-        
-    if !release_result.status.success() {
-        return Err(anyhow!(
-            "Failed to create GitHub release: {}", 
-            String::from_utf8_lossy(&release_result.stderr)
-        ));
+    // Describe every uploaded asset in the manifest
+    let mut manifest = ReleaseManifest::new(version, release_sha, &release.html_url)
+        .with_image(options.image.clone());
+    for path in assets {
+        let asset = client.upload_asset(&release, path).await?;
+        logger.info(&format!("📎 Uploaded {} ({} bytes)", asset.name, asset.size));
+        manifest.add_asset(path, &asset.browser_download_url)?;
     }
     
-    // Output release URL for action output
-    let release_url = format!(
-        "https://github.com/{}/releases/tag/{}", 
-        github_repository,
-        version
-    );
+    // Publish the manifest itself as an asset for installers
+    fs::write(&manifest_path, manifest.to_json()?)
+        .context("Failed to write release manifest")?;
+    let manifest_asset = client.upload_asset(&release, &manifest_path).await?;
+    logger.info(&format!("📋 Published release manifest: {}", manifest_asset.browser_download_url));
     
-    github::set_output("release_url", &release_url);
-    logger.info(&format!("✅ Release created successfully: {}", release_url));
+    // Output release and manifest URLs for action output
+    github::set_output("release_url", &release.html_url);
+    github::set_output("manifest_url", &manifest_asset.browser_download_url);
     
     // Add explanation of version scheme
     logger.info("Version Scheme Explanation:");
@@ -353,8 +438,10 @@ mod tests {
 
     #[test]
     fn test_is_beta_version() {
-        assert_eq!(is_beta_version("beta-v0.0.1"), true);
-        assert_eq!(is_beta_version("stable-v0.0.1"), false);
-        assert_eq!(is_beta_version("v0.0.1"), false);
+        assert!(is_beta_version("beta-v0.0.1"));
+        assert!(!is_beta_version("stable-v0.0.1"));
+        assert!(!is_beta_version("v0.0.1"));
+        assert!(is_beta_version("v0.1.0-beta.2"));
+        assert!(!is_beta_version("beta-release"));
     }
 }
@@ -1,44 +1,73 @@
 //! Packages release assets for create-release action
-//! Used by: ./.github/actions/create-release/action.yml
+//! Used by: ./.github/actions/build-release/action.yml
 //! Purpose: Creates distribution archive and checksums
+//!
+//! The tarball and checksum are written to INPUT_OUTPUT_DIR (the working
+//! directory by default) and their paths output as `assets`.
 
 use anyhow::{Context, Result};
-use github_workflow_scripts::{get_logger, init};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::{get_logger, github, init};
 use sha2::{Sha256, Digest};
-use std::{fs, path::Path, process::Command};
+use std::{env, fs, path::{Path, PathBuf}, process::Command};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init();
     let logger = get_logger(false);
+    let dry_run = DryRun::from_env("package_assets");
 
     logger.info("📦 Starting asset packaging...");
 
+    // Keep the assets out of the checkout when asked to
+    let output_dir = env::var("INPUT_OUTPUT_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    if !dry_run.is_enabled() {
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    }
+    let output_dir = output_dir.canonicalize().unwrap_or(output_dir);
+    let tarball = output_dir.join("dev-environment.tar.gz");
+    let checksum = output_dir.join("checksum.txt");
+
     // Create staging area
     let staging_dir = Path::new("release_assets");
-    fs::create_dir_all(staging_dir)?;
+    if !dry_run.is_enabled() {
+        fs::create_dir_all(staging_dir)?;
 
-    // Create required directories if they don't exist
-    for dir in &["distributions", "startup", "docs"] {
-        fs::create_dir_all(dir)
-            .context(format!("Failed to create {} directory", dir))?;
+        // Create required directories if they don't exist
+        for dir in &["distributions", "startup", "docs"] {
+            fs::create_dir_all(dir)
+                .context(format!("Failed to create {} directory", dir))?;
+        }
     }
 
     // Copy distribution files
     logger.info("Copying distribution files...");
     for dir in &["distributions", "startup", "docs"] {
         let target = staging_dir.join(dir);
-        fs::create_dir_all(&target)?;
+        if !dry_run.is_enabled() {
+            fs::create_dir_all(&target)?;
+        }
         
         // Only copy if directory exists and has contents
         if Path::new(dir).exists() && fs::read_dir(dir)?.next().is_some() {
-            let status = Command::new("cp")
-                .args(["-r", &format!("{}/*", dir), &target.to_string_lossy()])
+            // cp runs without a shell, so `dir/.` names the contents instead of a glob
+            let mut copy = Command::new("cp");
+            copy.args(["-r", &format!("{}/.", dir), &target.to_string_lossy()]);
+            if dry_run.intercept_command(&format!("Copy {}", dir), &copy)? {
+                continue;
+            }
+
+            let status = copy
                 .status()
                 .context(format!("Failed to copy {}", dir))?;
 
             if !status.success() {
-                logger.warn(&format!("No contents to copy from {}", dir));
+                anyhow::bail!("Failed to copy {} into the release assets", dir);
             }
         } else {
             logger.info(&format!("Directory {} is empty or doesn't exist, skipping", dir));
@@ -48,21 +77,46 @@ async fn main() -> Result<()> {
     // Copy Docker files
     logger.info("Copying Docker files...");
     for pattern in &["Dockerfile*", "docker-compose*.yml"] {
-        match Command::new("cp")
-            .args(["-v", pattern, &staging_dir.to_string_lossy()])
-            .status()
-        {
-            Ok(_) => logger.info(&format!("Copied {}", pattern)),
-            Err(_) => logger.info(&format!("No {} found", pattern)),
+        // Expand the pattern here, since no shell does it for cp
+        let files = glob::glob(pattern)
+            .with_context(|| format!("Invalid pattern {}", pattern))?
+            .collect::<Result<Vec<PathBuf>, _>>()
+            .with_context(|| format!("Failed to list {}", pattern))?;
+        if files.is_empty() {
+            logger.info(&format!("No {} found", pattern));
+            continue;
         }
+
+        let mut copy = Command::new("cp");
+        copy.arg("-v").args(&files).arg(staging_dir);
+        if dry_run.intercept_command(&format!("Copy {}", pattern), &copy)? {
+            continue;
+        }
+
+        let status = copy.status().context(format!("Failed to copy {}", pattern))?;
+        if !status.success() {
+            anyhow::bail!("Failed to copy {} into the release assets", pattern);
+        }
+        logger.info(&format!("Copied {}", pattern));
     }
 
     // Create tarball even if some directories were empty
     logger.info("Creating tarball...");
-    let output = Command::new("tar")
-        .current_dir(staging_dir)
-        .args(["-czf", "../dev-environment.tar.gz", "."])
-        .output()?;
+    let mut tar = Command::new("tar");
+    tar.current_dir(staging_dir)
+        .arg("-czf")
+        .arg(&tarball)
+        .arg(".");
+
+    // Without a tarball there is nothing to checksum, so a dry run stops here
+    if dry_run.intercept_command("Create tarball", &tar)? {
+        dry_run.record("Write checksum", PlannedAction::WriteFile { path: checksum.display().to_string() })?;
+        dry_run.record("Clean up staging directory", PlannedAction::RemovePath { path: staging_dir.display().to_string() })?;
+        logger.info("📦 Dry run complete, no assets were packaged");
+        return Ok(());
+    }
+
+    let output = tar.output()?;
 
     if !output.status.success() {
         anyhow::bail!("Failed to create tarball");
@@ -70,20 +124,23 @@ async fn main() -> Result<()> {
 
     // Generate checksum
     logger.info("Generating checksum...");
-    let tar_contents = fs::read("dev-environment.tar.gz")?;
+    let tar_contents = fs::read(&tarball)?;
     let mut hasher = Sha256::new();
     hasher.update(&tar_contents);
     let hash = format!("{:x}", hasher.finalize());
     
-    fs::write("checksum.txt", format!("{} dev-environment.tar.gz\n", hash))?;
+    fs::write(&checksum, format!("{} dev-environment.tar.gz\n", hash))?;
 
     // Clean up staging directory
     fs::remove_dir_all(staging_dir)?;
 
     // Log completion status
-    let tar_size = fs::metadata("dev-environment.tar.gz")?.len();
+    let tar_size = fs::metadata(&tarball)?.len();
     logger.info(&format!("📦 Package complete! Tarball size: {} bytes", tar_size));
     logger.info(&format!("SHA256: {}", hash));
 
+    // Hand the packaged files to create-release
+    github::set_output("assets", &format!("{} {}", tarball.display(), checksum.display()));
+
     Ok(())
 }
@@ -0,0 +1,196 @@
+//! Beta to stable promotion script for promote-release action
+//! Used by: ./.github/actions/promote-release/action.yml
+//! Purpose: Verifies a beta release (commit on main, release and manifest with
+//! image digest and asset checksums) and re-tags its exact Docker image as
+//! stable, so the stable release ships what was tested instead of a rebuild.
+//! The beta's assets are downloaded to INPUT_OUTPUT_DIR, checked against the
+//! manifest and output as `assets` for the stable release.
+
+use anyhow::{bail, Context, Result};
+use github_workflow_scripts::{get_logger, init, github, Logger};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::release::manifest::{sha256_file, ReleaseManifest, MANIFEST_FILE};
+use github_workflow_scripts::release::promotion::{self, Check, Verification};
+use github_workflow_scripts::release::ReleaseClient;
+use github_workflow_scripts::version::{Channel, Version};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init();
+    let logger = get_logger(false);
+    let dry_run = DryRun::from_env("promote_release");
+
+    let github_token = env::var("INPUT_GITHUB_TOKEN").context("Missing INPUT_GITHUB_TOKEN")?;
+    let github_repository = env::var("GITHUB_REPOSITORY")
+        .context("Missing GITHUB_REPOSITORY environment variable")?;
+    let target_branch = env::var("INPUT_TARGET_BRANCH")
+        .ok()
+        .filter(|branch| !branch.trim().is_empty())
+        .unwrap_or_else(|| "main".to_string());
+
+    if let Err(e) = Command::new("git").args(["fetch", "--tags", "origin", &target_branch]).output() {
+        logger.warn(&format!("Failed to fetch tags: {}", e));
+    }
+
+    // Promote the requested beta, or the latest one
+    let beta_tag = match env::var("INPUT_BETA_VERSION") {
+        Ok(tag) if !tag.trim().is_empty() => tag.trim().to_string(),
+        _ => latest_beta()?,
+    };
+    let beta: Version = beta_tag.parse()
+        .with_context(|| format!("Invalid beta version '{}'", beta_tag))?;
+    let stable = promotion::stable_version(&beta)?.to_string();
+    logger.info(&format!("🚀 Promoting {} to {}", beta_tag, stable));
+
+    let commit = git_output(&["rev-list", "-n", "1", &format!("refs/tags/{}", beta_tag)])
+        .with_context(|| format!("Tag {} does not exist", beta_tag))?;
+
+    let mut verification = Verification::default();
+    let stable_exists = git_output(&["rev-parse", "--verify", "--quiet", &format!("refs/tags/{}", stable)]).is_ok();
+    verification.push(Check::new(
+        "Stable tag free",
+        !stable_exists,
+        if stable_exists { format!("`{}` already exists", stable) } else { format!("`{}` is not tagged yet", stable) },
+    ));
+
+    let target = format!("origin/{}", target_branch);
+    let on_target = Command::new("git")
+        .args(["merge-base", "--is-ancestor", &commit, &target])
+        .status()
+        .context("Failed to check commit ancestry")?
+        .success();
+    verification.push(Check::new(
+        "Commit on target branch",
+        on_target,
+        format!("`{}` {} `{}`", &commit[..7], if on_target { "is in" } else { "is not in" }, target),
+    ));
+
+    // The manifest is the record of what the beta shipped
+    let client = ReleaseClient::new(&github_token, &github_repository)?;
+    let mut manifest = None;
+    let beta_release = client.find_by_tag(&beta_tag).await?;
+    match &beta_release {
+        Some(release) => {
+            verification.push(Check::new("Release exists", true, release.html_url.clone()));
+            match release.asset(MANIFEST_FILE) {
+                Some(asset) => {
+                    let contents = client.download_asset(asset).await?;
+                    let parsed: ReleaseManifest = serde_json::from_slice(&contents)
+                        .with_context(|| format!("Failed to parse {} of {}", MANIFEST_FILE, beta_tag))?;
+                    for check in promotion::verify_release(release, &parsed, &commit) {
+                        verification.push(check);
+                    }
+                    manifest = Some(parsed);
+                },
+                None => verification.push(promotion::missing_manifest(release)),
+            }
+        },
+        None => verification.push(Check::new("Release exists", false, format!("no release for `{}`", beta_tag))),
+    }
+
+    github::add_step_summary(&verification.to_markdown(&beta_tag, &stable));
+    report(&verification, logger.as_ref());
+    let (release, manifest, image) = match (beta_release, manifest) {
+        (Some(release), Some(manifest)) if verification.passed() => match manifest.image.clone() {
+            Some(image) => (release, manifest, image),
+            None => bail!("Promotion of {} to {} is blocked by failed checks", beta_tag, stable),
+        },
+        _ => bail!("Promotion of {} to {} is blocked by failed checks", beta_tag, stable),
+    };
+
+    // Point the stable tags at the beta's image instead of rebuilding it
+    let mut tags: Vec<String> = env::var("INPUT_STABLE_TAGS")
+        .unwrap_or_else(|_| "stable latest".to_string())
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    tags.push(stable.clone());
+    let mut retag = promotion::retag_command(&image, &tags);
+    if !dry_run.intercept_command("Re-tag image as stable", &retag)? {
+        let status = retag.status().context("Failed to re-tag image")?;
+        if !status.success() {
+            bail!("Failed to re-tag {}@{}", image.reference, image.digest);
+        }
+        logger.info(&format!("🏷️ Tagged {}@{} as {}", image.reference, image.digest, tags.join(", ")));
+    }
+
+    // Ship the beta's assets as tested, outside the checkout create-release cleans
+    let output_dir = env::var("INPUT_OUTPUT_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::var_os("RUNNER_TEMP").map(PathBuf::from).unwrap_or_else(env::temp_dir).join("release-assets"));
+    if !dry_run.is_enabled() {
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    }
+    let mut assets = Vec::new();
+    for entry in &manifest.assets {
+        let path = output_dir.join(&entry.name);
+        assets.push(path.display().to_string());
+        if dry_run.intercept("Download beta asset", PlannedAction::WriteFile { path: path.display().to_string() })? {
+            continue;
+        }
+
+        let asset = release.asset(&entry.name)
+            .with_context(|| format!("{} is no longer attached to {}", entry.name, beta_tag))?;
+        fs::write(&path, client.download_asset(asset).await?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        let (sha256, _) = sha256_file(&path)?;
+        if sha256 != entry.sha256 {
+            bail!("{} of {} does not match the checksum in its manifest", entry.name, beta_tag);
+        }
+        logger.info(&format!("📎 Downloaded {} from {}", entry.name, beta_tag));
+    }
+    let assets = assets.join(" ");
+
+    let stable_image = format!("{}:{}", promotion::image_repository(&image.reference), stable);
+    for (name, value) in [
+        ("stable_version", stable.as_str()),
+        ("promoted_from", beta_tag.as_str()),
+        ("commit", commit.as_str()),
+        ("image", stable_image.as_str()),
+        ("image_digest", image.digest.as_str()),
+        ("assets", assets.as_str()),
+    ] {
+        dry_run.record("Set output", PlannedAction::Output { name: name.to_string(), value: value.to_string() })?;
+        github::set_output(name, value);
+    }
+
+    logger.info(&format!("✅ {} promoted to {}", beta_tag, stable));
+    Ok(())
+}
+
+/// Latest beta tag in the clone
+fn latest_beta() -> Result<String> {
+    let tags = git_output(&["tag", "-l", &format!("{}*", Channel::Beta.prefix())]).unwrap_or_default();
+    match Version::latest(tags.lines(), Channel::Beta) {
+        Some((_, tag)) => Ok(tag.to_string()),
+        None => bail!("No beta release to promote"),
+    }
+}
+
+/// Trimmed stdout of a successful git command
+fn git_output(args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).output().context("Failed to run git")?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || stdout.is_empty() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(stdout)
+}
+
+fn report(verification: &Verification, logger: &dyn Logger) {
+    for check in &verification.checks {
+        let message = format!("{}: {}", check.name, check.detail);
+        if check.passed {
+            logger.info(&format!("✅ {}", message));
+        } else {
+            logger.error(&format!("❌ {}", message));
+        }
+    }
+}
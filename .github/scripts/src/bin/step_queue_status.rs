@@ -0,0 +1,44 @@
+//! Release queue status rendering script
+//!
+//! Used by: ./.github/actions/notify-release-status/action.yml
+//! Purpose: Renders the branch queue as a job summary, PR comment body or terminal table
+//!
+//! Inputs:
+//! - INPUT_BRANCH: release branch whose queue is shown
+//! - INPUT_SHA: commit to highlight, optional
+//! - INPUT_FORMAT: summary (default), comment or terminal
+//!
+//! Outputs:
+//! - body: the rendered comment, for the comment format
+
+use anyhow::{bail, Context, Result};
+use github_workflow_scripts::{get_logger, github, init};
+use github_workflow_scripts::release_queue::ReleaseQueue;
+use std::env;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init();
+    let logger = get_logger(false);
+
+    let branch = env::var("INPUT_BRANCH").context("Missing INPUT_BRANCH")?;
+    let sha = env::var("INPUT_SHA").ok().filter(|sha| !sha.is_empty());
+    let format = env::var("INPUT_FORMAT").unwrap_or_else(|_| "summary".to_string());
+
+    let mut report = ReleaseQueue::open_default()?.report(&branch)?;
+    if let Some(sha) = &sha {
+        report = report.with_highlight(sha);
+    }
+
+    match format.as_str() {
+        "summary" => {
+            github::add_step_summary(&report.to_markdown());
+            logger.info(&format!("📋 Added {} queue status to the job summary", branch));
+        },
+        "comment" => github::set_multiline_output("body", &report.to_comment()),
+        "terminal" => print!("{}", report.to_terminal()),
+        other => bail!("Unknown format '{}': expected summary, comment or terminal", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+//! Release rollback script for rollback-release action
+//! Used by: ./.github/actions/rollback-release/action.yml
+//! Purpose: Undoes a failed or bad release: deletes the GitHub release, the
+//! remote tag and optionally the Docker Hub version tag, then returns the
+//! release's queue entries to pending so the next run releases them again
+//!
+//! With `--branch-only` it just outputs the release branch of INPUT_VERSION
+//! as `branch`, so the action can check out that branch's queue first.
+
+use anyhow::{anyhow, Context, Result};
+use github_workflow_scripts::{get_logger, init, github, Logger};
+use github_workflow_scripts::dockerhub::{DockerHubClient, HUB_URL};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::release::ReleaseClient;
+use github_workflow_scripts::release_queue::ReleaseQueue;
+use github_workflow_scripts::version::{Channel, Version};
+use std::env;
+use std::process::Command;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init();
+    let logger = get_logger(false);
+    let dry_run = DryRun::from_env("rollback_release");
+
+    let version = env::var("INPUT_VERSION").context("Missing INPUT_VERSION")?;
+    let version = version.trim().to_string();
+    let branch = release_branch(&version)?;
+    if env::args().any(|arg| arg == "--branch-only") {
+        github::set_output("branch", branch);
+        return Ok(());
+    }
+
+    let github_token = env::var("INPUT_GITHUB_TOKEN").context("Missing INPUT_GITHUB_TOKEN")?;
+    let github_repository = env::var("GITHUB_REPOSITORY")
+        .context("Missing GITHUB_REPOSITORY environment variable")?;
+    let delete_image = env::var("INPUT_DELETE_IMAGE")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    logger.info(&format!("⏪ Rolling back {} on {}", version, branch));
+    if dry_run.is_enabled() {
+        logger.info("Dry run: nothing will be deleted or requeued");
+    }
+
+    // Resolve the tag before deleting it; a release without a tag may name its commit
+    let commit = tag_commit(&version, logger.as_ref())?
+        .or_else(|| env::var("INPUT_COMMIT").ok().filter(|sha| !sha.trim().is_empty()));
+
+    let mut summary = vec![format!("## ⏪ Rollback of `{}`", version), String::new()];
+
+    // The release goes first: deleting its tag first would turn it into a draft
+    let client = ReleaseClient::new(&github_token, &github_repository)?;
+    match client.find_by_tag(&version).await? {
+        Some(release) => {
+            let delete = PlannedAction::Api {
+                method: "DELETE".to_string(),
+                route: format!("{}/{}", client.releases_route(), release.id),
+            };
+            if !dry_run.intercept("Delete GitHub release", delete)? {
+                client.delete(&release).await?;
+                logger.info(&format!("🗑️ Deleted release {}", release.html_url));
+            }
+            summary.push(format!("- GitHub release: deleted {}", release.html_url));
+        },
+        None => {
+            logger.info(&format!("No GitHub release for {}", version));
+            summary.push("- GitHub release: none".to_string());
+        },
+    }
+
+    summary.push(delete_tag(&version, &dry_run, logger.as_ref())?);
+
+    if delete_image {
+        summary.push(delete_image_tag(&version, &dry_run, logger.as_ref()).await?);
+    }
+
+    // Send the commits the release shipped back to the queue
+    let queue = ReleaseQueue::open_default()?;
+    let restored = if dry_run.is_enabled() {
+        let restored = queue.rollback_preview(branch, &version, commit.as_deref())?;
+        if !restored.is_empty() {
+            dry_run.record("Requeue released commits", PlannedAction::WriteFile {
+                path: queue.path_for(branch).display().to_string(),
+            })?;
+        }
+        restored
+    } else {
+        queue.rollback(branch, &version, commit.as_deref())?
+    };
+    for item in &restored {
+        logger.info(&format!("↩️ Returned {} to pending", item.commit));
+    }
+    summary.push(format!("- Queue: {} commit(s) returned to pending on {}", restored.len(), branch));
+
+    github::set_output("requeued", &restored.len().to_string());
+    github::set_output("commit", commit.as_deref().unwrap_or_default());
+    github::add_step_summary(&(summary.join("\n") + "\n"));
+    logger.info("✅ Rollback complete");
+
+    Ok(())
+}
+
+/// Release branch a version was cut from, following its release channel
+fn release_branch(version: &str) -> Result<&'static str> {
+    let parsed: Version = version.parse()
+        .with_context(|| format!("Cannot roll back '{}': not a release version", version))?;
+    Ok(match parsed.release_channel() {
+        Channel::Beta => "beta",
+        Channel::Stable => "main",
+    })
+}
+
+/// Commit the tag points at, if the tag exists locally or on the remote
+fn tag_commit(version: &str, logger: &dyn Logger) -> Result<Option<String>> {
+    if let Err(e) = Command::new("git").args(["fetch", "--tags", "--force"]).output() {
+        logger.warn(&format!("Failed to fetch tags: {}", e));
+    }
+
+    let output = Command::new("git")
+        .args(["rev-list", "-n", "1", &format!("refs/tags/{}", version)])
+        .output()
+        .context("Failed to resolve tag")?;
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(Some(sha).filter(|sha| output.status.success() && !sha.is_empty()))
+}
+
+/// Delete the tag on the remote and in the local clone
+fn delete_tag(version: &str, dry_run: &DryRun, logger: &dyn Logger) -> Result<String> {
+    let tag_ref = format!("refs/tags/{}", version);
+    let remote = Command::new("git")
+        .args(["ls-remote", "--tags", "origin", &tag_ref])
+        .output()
+        .context("Failed to list remote tags")?;
+
+    let on_remote = !String::from_utf8_lossy(&remote.stdout).trim().is_empty();
+    if !on_remote {
+        logger.info(&format!("Tag {} is not on the remote", version));
+    } else {
+        let mut push = Command::new("git");
+        push.args(["push", "origin", "--delete", &tag_ref]);
+        if !dry_run.intercept_command("Delete remote tag", &push)? {
+            let status = push.status().context("Failed to delete remote tag")?;
+            if !status.success() {
+                return Err(anyhow!("Failed to delete remote tag {}", version));
+            }
+            logger.info(&format!("🗑️ Deleted remote tag {}", version));
+        }
+    }
+
+    let mut local = Command::new("git");
+    local.args(["tag", "-d", version]);
+    if !dry_run.intercept_command("Delete local tag", &local)? {
+        // A clone without the tag has nothing to delete
+        let _ = local.output();
+    }
+
+    if on_remote {
+        Ok(format!("- Tag: deleted `{}`", version))
+    } else {
+        Ok(format!("- Tag: `{}` not on the remote", version))
+    }
+}
+
+/// Delete the version tag of the image from Docker Hub
+///
+/// The repository defaults to DOCKER_IMAGE, which the release build pushes
+/// the version tag to.
+async fn delete_image_tag(version: &str, dry_run: &DryRun, logger: &dyn Logger) -> Result<String> {
+    let repository = env::var("INPUT_IMAGE_REPOSITORY")
+        .ok()
+        .filter(|repository| !repository.trim().is_empty())
+        .or_else(|| env::var("DOCKER_IMAGE").ok())
+        .context("Set INPUT_IMAGE_REPOSITORY or DOCKER_IMAGE to delete the image tag")?;
+
+    let delete = PlannedAction::Api {
+        method: "DELETE".to_string(),
+        route: format!("{}{}", HUB_URL, DockerHubClient::tag_route(&repository, version)),
+    };
+    if dry_run.intercept("Delete Docker Hub tag", delete)? {
+        return Ok(format!("- Docker image: deleted `{}:{}`", repository, version));
+    }
+
+    let username = env::var("DOCKERHUB_USERNAME").context("Missing DOCKERHUB_USERNAME")?;
+    let token = env::var("DOCKERHUB_TOKEN").context("Missing DOCKERHUB_TOKEN")?;
+    let hub = DockerHubClient::login(&username, &token).await?;
+
+    if hub.delete_tag(&repository, version).await? {
+        logger.info(&format!("🗑️ Deleted image {}:{}", repository, version));
+        Ok(format!("- Docker image: deleted `{}:{}`", repository, version))
+    } else {
+        logger.info(&format!("Image {}:{} does not exist", repository, version));
+        Ok(format!("- Docker image: `{}:{}` not found", repository, version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_branch_follows_channel() {
+        assert_eq!(release_branch("beta-v1.2.3").unwrap(), "beta");
+        assert_eq!(release_branch("v1.2.3-beta.4").unwrap(), "beta");
+        assert_eq!(release_branch("stable-v1.2.3").unwrap(), "main");
+        assert_eq!(release_branch("v1.2.3").unwrap(), "main");
+        assert_eq!(release_branch("v1.2.3-rc.1").unwrap(), "main");
+        assert!(release_branch("latest").is_err());
+    }
+}
@@ -1,16 +1,17 @@
 use anyhow::{Context, Result};
 use github_workflow_scripts::{get_logger, init};
+use github_workflow_scripts::release_queue::ReleaseQueue;
+use github_workflow_scripts::signing::{Gpg, SigningMethod};
+use std::path::Path;
+use std::process::Stdio;
 use std::{env, process::Command};
-use std::io::Write;
 
 // Struct to hold common dependencies and state
 struct CommitSigner {
     logger: Box<dyn github_workflow_scripts::Logger>,
     sha: String,
-    position: String,
-    remaining: String,
-    est_time: String,
-    passphrase: String,
+    branch: String,
+    passphrase: Option<String>,
 }
 
 impl CommitSigner {
@@ -20,36 +21,48 @@ impl CommitSigner {
         
         // Get all required inputs upfront
         let sha = env::var("INPUT_SHA").context("Missing SHA input")?;
-        let position = env::var("QUEUE_POSITION").context("Missing queue position")?;
-        let remaining = env::var("QUEUE_REMAINING").context("Missing remaining items")?;
-        let est_time = env::var("QUEUE_ESTIMATED_TIME").context("Missing estimated time")?;
-        let passphrase = env::var("INPUT_BOT_GPG_PASSPHRASE").context("Missing GPG passphrase")?;
+        let branch = env::var("INPUT_BRANCH").context("Missing branch input")?;
+        let passphrase = env::var("INPUT_BOT_GPG_PASSPHRASE").ok().filter(|passphrase| !passphrase.is_empty());
 
         Ok(Self {
             logger,
             sha,
-            position,
-            remaining,
-            est_time,
+            branch,
             passphrase,
         })
     }
 
-    // Add method to verify GPG setup
+    // Signing method configured by setup-git-signing; git defaults to openpgp
+    fn signing_method(&self) -> Result<SigningMethod> {
+        let output = Command::new("git")
+            .args(["config", "--get", "gpg.format"])
+            .output()
+            .context("Failed to read gpg.format")?;
+
+        String::from_utf8_lossy(&output.stdout).parse()
+    }
+
+    // Verify the signing key and cache its passphrase in the agent
     fn verify_gpg_setup(&self) -> Result<()> {
+        // SSH keys and X.509 identities are not GPG keys; git signs with them
+        // as configure_git set up
+        let method = self.signing_method()?;
+        if method != SigningMethod::Gpg {
+            self.logger.info(&format!("🔍 Signing with {}, skipping the GPG key check", method));
+            return Ok(());
+        }
+
         self.logger.info("🔍 Verifying GPG setup...");
-        
-        let output = Command::new("gpg")
-            .args(["--list-secret-keys"])
-            .output()
-            .context("Failed to list GPG keys")?;
 
-        if !output.status.success() {
-            self.logger.warn("No GPG keys found");
-            anyhow::bail!("GPG verification failed: No keys available");
+        // GNUPGHOME is inherited from the setup-git-signing step
+        let gpg = Gpg::from_env();
+        let key = gpg.signing_key(Path::new(".")).context("GPG verification failed")?;
+        if let Some(passphrase) = &self.passphrase {
+            gpg.preset_passphrase(&key, passphrase)?;
         }
+        gpg.check_signing(&key)?;
 
-        self.logger.info("✅ GPG setup verified");
+        self.logger.info(&format!("✅ GPG setup verified for key {}", key.key_id));
         Ok(())
     }
 
@@ -70,45 +83,26 @@ impl CommitSigner {
         Ok(())
     }
 
-    // Modified create_signed_commit with improved GPG handling
     fn create_signed_commit(&self) -> Result<()> {
         self.logger.info("🔏 Creating signed commit...");
 
-        // Verify GPG setup first
-        self.verify_gpg_setup()?;
-
         // Prepare commit message
-        let commit_msg = format!(
-            "📦 Queue release for {}\n\nQueue Status:\n- Position: {}\n- Items needed: {} more\n- Estimated time: {}",
-            self.sha, self.position, self.remaining, self.est_time
-        );
+        let report = ReleaseQueue::open_default()?.report(&self.branch)?.with_highlight(&self.sha);
+        let commit_msg = format!("📦 Queue release for {}\n\n{}", self.sha, report.to_terminal());
 
-        // Create the commit with signature
-        let mut child = Command::new("git")
+        // Git gets no passphrase: the signing program takes it from its agent.
+        // The signing format and program come from the git config
+        let output = Command::new("git")
             .args([
-                "-c", "gpg.program=gpg",  // Ensure using gpg explicitly
                 "-c", "commit.gpgsign=true",
                 "commit",
                 "-S",
                 "-m", &commit_msg,
                 "--allow-empty"
             ])
-            .env("GPG_TTY", "/dev/tty")
-            .env("GNUPGHOME", std::env::var("HOME").unwrap_or_default() + "/.gnupg")
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-            .context("Failed to spawn git commit")?;
-
-        // Provide passphrase if needed
-        if let Some(mut stdin) = child.stdin.take() {
-            writeln!(stdin, "{}", self.passphrase)
-                .context("Failed to write GPG passphrase")?;
-        }
-
-        let output = child.wait_with_output()
-            .context("Failed to complete git commit")?;
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run git commit")?;
 
         if !output.status.success() {
             let error = String::from_utf8_lossy(&output.stderr);
@@ -120,7 +114,7 @@ impl CommitSigner {
         Ok(())
     }
 
-    // Modified run method to include verification
+    // Verify signing before touching the index
     async fn run(&self) -> Result<()> {
         self.verify_gpg_setup()?;
         self.stage_changes()?;
@@ -1,63 +1,72 @@
 //! Documentation update script
-//! 
-//! Updates version references in documentation files
+//!
+//! Stamps the release version into the documentation files configured in
+//! `.github/docs-version.json`. With `INPUT_MODE=check` nothing is written and
+//! the step fails if any file still mentions another version.
 
-use anyhow::{Context, Result};
-use std::path::Path; // Removed unused PathBuf import
+use anyhow::{anyhow, bail, Context, Result};
 use std::fs;
-use github_workflow_scripts::{init, get_logger};
+use std::path::PathBuf;
+use github_workflow_scripts::{init, get_logger, github};
+use github_workflow_scripts::docs::{StampConfig, Stamper, CONFIG_FILE};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::version::Version;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize our custom logger
     init();
     let logger = get_logger(false);
-    
+
     logger.info("📚 Updating documentation...");
-    
+
     // Get version from environment or read from VERSION file
-    let version = std::env::var("INPUT_VERSION")
-        .unwrap_or_else(|_| {
-            fs::read_to_string("VERSION")
-                .unwrap_or_else(|_| "v0.1.0".to_string())
-                .trim()
-                .to_string()
-        });
-    
-    // Update documentation files
-    update_file("README.md", &version)?;
-    update_file("docs/installation.md", &version)?;
-    update_file("docs/development.md", &version)?;
-    
-    logger.info("✅ Documentation updated successfully!");
-    
-    Ok(())
-}
+    let version = match std::env::var("INPUT_VERSION") {
+        Ok(version) if !version.trim().is_empty() => version,
+        _ => fs::read_to_string("VERSION")
+            .map_err(|_| anyhow!("Missing INPUT_VERSION and no VERSION file"))?,
+    };
+    let version: Version = version.trim().parse()
+        .with_context(|| format!("Invalid version '{}'", version.trim()))?;
 
-// Updates version references in a file
-fn update_file(path: impl AsRef<Path>, version: &str) -> Result<()> {
-    let path = path.as_ref();
-    let logger = get_logger(false);
-    
-    // Skip if file doesn't exist
-    if !path.exists() {
-        return Ok(());
+    let check = match std::env::var("INPUT_MODE").unwrap_or_default().trim() {
+        "" | "write" => false,
+        "check" => true,
+        other => bail!("Unknown mode '{}': expected write or check", other),
+    };
+
+    // Paths in the configuration are relative to the repository root
+    let root = std::env::var_os("GITHUB_WORKSPACE").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+    let config_path = std::env::var("INPUT_CONFIG").unwrap_or_else(|_| CONFIG_FILE.to_string());
+    let config = StampConfig::load(&root.join(config_path))?;
+    let stamper = Stamper::new(&root, &config)?;
+
+    let stale = stamper.stale_files(&version)?;
+    let dry_run = DryRun::from_env("update_docs");
+    for file in &stale {
+        for (old, new) in &file.replacements {
+            logger.info(&format!("{}: {} -> {}", file.path.display(), old, new));
+        }
+        if check {
+            continue;
+        }
+        let write = PlannedAction::WriteFile { path: file.path.display().to_string() };
+        if !dry_run.intercept("Update version references", write)? {
+            stamper.write(file)?;
+        }
     }
-    
-    // Read file content
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read {}", path.display()))?;
-    
-    // Replace version references
-    // This is a simplified example - actual regex patterns would be more complex
-    let updated = content.replace("v0.1.0", version)
-        .replace("VERSION=0.1.0", &format!("VERSION={}", version.trim_start_matches('v')));
-    
-    // Write updated content back
-    fs::write(path, updated)
-        .with_context(|| format!("Failed to write to {}", path.display()))?;
-    
-    logger.info(&format!("✅ Updated version to {} in {}", version, path.display()));
-    
+
+    let paths: Vec<String> = stale.iter().map(|file| file.path.display().to_string()).collect();
+    github::set_output("changed_count", &paths.len().to_string());
+    github::set_multiline_output("changed_files", &paths.join("\n"));
+
+    if stale.is_empty() {
+        logger.info(&format!("✅ Documentation already mentions {}", version));
+    } else if check {
+        bail!("Documentation does not mention {} yet: {}", version, paths.join(", "));
+    } else {
+        logger.info(&format!("✅ Stamped {} into {} file(s)", version, paths.len()));
+    }
+
     Ok(())
 }
@@ -1,184 +1,202 @@
 use anyhow::{Context, Result};
-use github_workflow_scripts::{get_logger, init};
-use serde::{Deserialize, Serialize};
-use serde_json;
+use github_workflow_scripts::{get_logger, github, init};
+use github_workflow_scripts::release_queue::commits::CommitVerifier;
+use github_workflow_scripts::release_queue::history;
+use github_workflow_scripts::release_queue::lifecycle::EntryState;
+use github_workflow_scripts::release_queue::{AddOutcome, QueueItem, ReleaseQueue};
 use std::env;
-use std::fs::{create_dir_all, File};
-use std::io::{Read, Write};
-use std::path::Path;
-
-// Data models for the queue
-#[derive(Debug, Serialize, Deserialize)]
-struct QueueItem {
-    commit: String,
-    date: String,
-    pr: Option<u64>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct QueueFile {
-    items: Vec<QueueItem>,
-}
 
 // Main manager for queue operations
 struct QueueManager {
     logger: Box<dyn github_workflow_scripts::Logger>,
     branch: String,
-    // Removed unused github_token field
+    queue: ReleaseQueue,
+    verifier: CommitVerifier,
 }
 
 impl QueueManager {
     // Create a new queue manager instance
     fn new() -> Result<Self> {
         let logger = get_logger(false);
-        
+
         // Get required environment variables
         let branch = env::var("INPUT_BRANCH").context("Missing branch input")?;
         // We still read the token to validate it exists, but don't store it since we don't use it
         let _github_token = env::var("GITHUB_TOKEN").context("Missing GitHub token")?;
-        
+
+        let queue = ReleaseQueue::open_default()?;
+
         Ok(Self {
             logger,
             branch,
+            queue,
+            verifier: CommitVerifier::current_dir(),
         })
     }
-    
-    // Get the file path for the queue based on branch
-    fn get_queue_file_path(&self) -> String {
-        format!(".github/release_queue/{}.json", self.branch)
-    }
-    
-    // Ensure the directory exists for the queue file
-    fn ensure_directory_exists(&self) -> Result<()> {
-        let path = Path::new(".github/release_queue");
-        if !path.exists() {
-            create_dir_all(path).context("Failed to create release_queue directory")?;
+
+    // Clear processed commits from the queue
+    fn clear_processed_commits(&self, processed_sha: &str, version: Option<&str>) -> Result<()> {
+        let drained = self.queue.drain_through(&self.branch, processed_sha, version)?;
+
+        if drained.is_empty() {
+            self.logger.warn(&format!("Processed commit {} not found in queue", processed_sha));
+            return Ok(());
         }
+
+        self.logger.info(&format!("Removed processed commit {} and {} older item(s)",
+            processed_sha, drained.len() - 1));
+
+        let status = self.queue.status(&self.branch)?;
+        self.logger.info(&format!("{} items remain in the queue", status.count));
+
         Ok(())
     }
-    
-    // Load the queue from the file system
-    fn load_queue(&self) -> Result<QueueFile> {
-        let path = self.get_queue_file_path();
-        let file_path = Path::new(&path);
-        
-        // If file doesn't exist, return an empty queue
-        if !file_path.exists() {
-            return Ok(QueueFile { items: Vec::new() });
+
+    // Record that a release started so its duration feeds future ETAs
+    fn mark_commit_started(&self, sha: &str) -> Result<bool> {
+        let started = self.queue.mark_started(&self.branch, sha)?;
+        if started {
+            self.logger.info(&format!("Marked commit {} as started", sha));
+        } else {
+            self.logger.warn(&format!("Commit {} not found in queue", sha));
         }
-        
-        // Read and parse the file
-        let mut file = File::open(file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        
-        let queue: QueueFile = serde_json::from_str(&contents)
-            .context("Failed to parse queue file")?;
-            
-        Ok(queue)
-    }
-    
-    // Save the queue to file
-    fn save_queue(&self, queue: &QueueFile) -> Result<()> {
-        self.ensure_directory_exists()?;
-        let path = self.get_queue_file_path();
-        let serialized = serde_json::to_string_pretty(queue)
-            .context("Failed to serialize queue data")?;
-            
-        let mut file = File::create(path)?;
-        file.write_all(serialized.as_bytes())?;
-        
-        Ok(())
+
+        Ok(started)
     }
-    
-    // Clear processed commits from the queue
-    fn clear_processed_commits(&self, processed_sha: &str) -> Result<()> {
-        let mut queue = self.load_queue()?;
-        
-        // Find the position of the processed commit
-        if let Some(pos) = queue.items.iter().position(|item| item.commit == processed_sha) {
-            self.logger.info(&format!("Removing processed commit {} and all older items", processed_sha));
-            
-            // Remove this commit and all older ones
-            queue.items.drain(0..=pos);
-            
-            self.save_queue(&queue)?;
-            self.logger.info(&format!("{} items remain in the queue", queue.items.len()));
-        } else {
-            self.logger.warn(&format!("Processed commit {} not found in queue", processed_sha));
+
+    // Move a commit to another lifecycle state
+    fn transition_commit(&self, sha: &str, state: EntryState, reason: Option<&str>, version: Option<&str>) -> Result<QueueItem> {
+        let item = self.queue.transition(&self.branch, sha, state, reason, version)?;
+
+        match item.failure_reason() {
+            Some(reason) => self.logger.warn(&format!("Commit {} failed: {}", sha, reason)),
+            None => self.logger.info(&format!("Commit {} is now {}", sha, item.state)),
         }
-        
-        Ok(())
+
+        Ok(item)
     }
-    
+
     // Add a commit to the queue
-    fn add_commit_to_queue(&self, sha: &str, pr_number: Option<u64>) -> Result<()> {
-        let mut queue = self.load_queue()?;
-        
-        // Check if SHA already exists
-        if queue.items.iter().any(|item| item.commit == sha) {
+    fn add_commit_to_queue(&self, sha: &str, pr_number: Option<u64>) -> Result<AddOutcome> {
+        self.verifier.verify_queueable(sha, &self.branch)?;
+        let outcome = self.queue.add(&self.branch, sha, pr_number)?;
+
+        if outcome.added {
+            self.logger.info(&format!("Added commit {} to queue position {}", sha, outcome.position));
+        } else {
             self.logger.info(&format!("Commit {} already in queue, skipping", sha));
-            return Ok(());
         }
-        
-        // Add new item
-        let timestamp = chrono::Utc::now().to_rfc3339();
-        queue.items.push(QueueItem {
-            commit: sha.to_string(),
-            date: timestamp,
-            pr: pr_number,
-        });
-        
-        self.save_queue(&queue)?;
-        self.logger.info(&format!("Added commit {} to queue position {}", sha, queue.items.len()));
-        
-        Ok(())
+
+        Ok(outcome)
     }
-    
+
+    // Drop commits that were force-pushed away or are already released
+    fn prune_queue(&self) -> Result<usize> {
+        let pruned = self.queue.prune(&self.branch, &self.verifier)?;
+
+        for (item, reason) in &pruned {
+            self.logger.info(&format!("Pruned commit {}: {}", item.commit, reason));
+        }
+        self.logger.info(&format!("Pruned {} commit(s) from the {} queue", pruned.len(), self.branch));
+
+        Ok(pruned.len())
+    }
+
+    // Return the commits shipped in a rolled back release to pending
+    fn requeue_release(&self, version: &str, commit: Option<&str>) -> Result<usize> {
+        let restored = self.queue.rollback(&self.branch, version, commit)?;
+
+        for item in &restored {
+            self.logger.info(&format!("Returned commit {} to pending", item.commit));
+        }
+
+        Ok(restored.len())
+    }
+
+    // Render the commits shipped in a release as Markdown
+    fn release_summary(&self, version: &str) -> Result<String> {
+        let entries = history::read(self.queue.dir())?;
+
+        history::render_summary(&entries, version)
+            .with_context(|| format!("No released commits recorded for {}", version))
+    }
+
     // Get status information about the queue
     fn get_queue_status(&self) -> Result<(usize, String)> {
-        let queue = self.load_queue()?;
-        
-        // Get queue position
-        let position = queue.items.len();
-        
-        // Create an empty string to use as default
-        let empty_date = String::new();
-        
-        // Fix the borrowing issue by creating a longer-lived value
-        let oldest = queue.items
-            .first()
-            .map(|item| &item.date)
-            .unwrap_or(&empty_date);
-        
-        Ok((position, oldest.clone()))
+        let status = self.queue.status(&self.branch)?;
+        let oldest = status.oldest.map(|date| date.to_rfc3339()).unwrap_or_default();
+
+        Ok((status.count, oldest))
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init();
-    
+
     let manager = QueueManager::new()?;
     let action = env::var("INPUT_ACTION").unwrap_or_else(|_| "status".to_string());
-    
+
     // Process the requested action
     match action.as_str() {
         "add" => {
             let sha = env::var("INPUT_SHA").context("Missing SHA input for add action")?;
             let pr_number = env::var("INPUT_PR_NUMBER").ok().and_then(|s| s.parse().ok());
-            manager.add_commit_to_queue(&sha, pr_number)?;
+            let outcome = manager.add_commit_to_queue(&sha, pr_number)?;
+            github::set_output("added", &outcome.added.to_string());
+            github::set_output("position", &outcome.position.to_string());
+        },
+        "start" => {
+            let sha = env::var("INPUT_SHA").context("Missing SHA input for start action")?;
+            let started = manager.mark_commit_started(&sha)?;
+            github::set_output("started", &started.to_string());
+        },
+        "transition" => {
+            let sha = env::var("INPUT_SHA").context("Missing SHA input for transition action")?;
+            let state: EntryState = env::var("INPUT_STATE").context("Missing state input for transition action")?.parse()?;
+            let reason = env::var("INPUT_REASON").ok();
+            let version = env::var("INPUT_VERSION").ok().filter(|v| !v.is_empty());
+            let item = manager.transition_commit(&sha, state, reason.as_deref(), version.as_deref())?;
+            github::set_output("state", item.state.as_str());
+            github::set_output("retries", &item.retries.to_string());
+        },
+        "prune" => {
+            let pruned = manager.prune_queue()?;
+            github::set_output("pruned", &pruned.to_string());
         },
         "clear" => {
             let processed_sha = env::var("INPUT_PROCESSED_SHA").context("Missing processed SHA")?;
-            manager.clear_processed_commits(&processed_sha)?;
+            let version = env::var("INPUT_VERSION").ok().filter(|v| !v.is_empty());
+            manager.clear_processed_commits(&processed_sha, version.as_deref())?;
+        },
+        "rollback" => {
+            let version = env::var("INPUT_VERSION").context("Missing version input for rollback action")?;
+            let commit = env::var("INPUT_SHA").ok().filter(|sha| !sha.is_empty());
+            let requeued = manager.requeue_release(&version, commit.as_deref())?;
+            github::set_output("requeued", &requeued.to_string());
+        },
+        "summary" => {
+            let version = env::var("INPUT_VERSION").context("Missing version input for summary action")?;
+            let summary = manager.release_summary(&version)?;
+            github::set_multiline_output("summary", &summary);
         },
-        "status" | _ => {
+        _ => {
             let (count, oldest) = manager.get_queue_status()?;
-            println!("::set-output name=count::{}", count);
-            println!("::set-output name=oldest::{}", oldest);
+            github::set_output("count", &count.to_string());
+            github::set_output("oldest", &oldest);
+
+            let queue = manager.queue.load(&manager.branch)?;
+            let failed = queue.items.iter().filter(|item| item.state == EntryState::Failed).count();
+            github::set_output("failed", &failed.to_string());
+            let next = queue.items.iter().find(|item| item.state == EntryState::Pending);
+            github::set_output("next", next.map(|item| item.commit.as_str()).unwrap_or_default());
+
+            let readiness = manager.queue.readiness(&manager.branch)?;
+            github::set_output("remaining", &readiness.remaining_items.to_string());
+            github::set_output("ready_to_release", &readiness.ready.to_string());
+            github::set_output("ready_reason", &readiness.reasons.join("; "));
         }
     }
-    
+
     Ok(())
 }
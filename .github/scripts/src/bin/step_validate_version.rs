@@ -1,10 +1,15 @@
 //! Version validation script for create-release action
 //! Used by: ./.github/actions/create-release/action.yml
 //! Purpose: Validates and normalizes version strings for releases
+//!
+//! Accepts both the prefixed scheme produced by the version determiner
+//! (`beta-v1.2.3`, `stable-v1.2.3`) and the plain scheme (`v1.2.3`,
+//! `v1.2.3-beta.N`). INPUT_SCHEME (`prefixed` or `plain`) selects the form of
+//! VALIDATED_VERSION; by default the input keeps its own scheme.
 
 use anyhow::{Context, Result};
-use github_workflow_scripts::get_logger;
-use regex::Regex;
+use github_workflow_scripts::{get_logger, github};
+use github_workflow_scripts::version::{Scheme, Version};
 use serde::{Deserialize, Serialize};
 use std::env;
 
@@ -12,6 +17,8 @@ use std::env;
 struct VersionInfo {
     raw: String,
     normalized: String,
+    prefixed: String,
+    plain: String,
     is_valid: bool,
     is_prerelease: bool,
 }
@@ -21,35 +28,39 @@ impl VersionInfo {
         Self {
             raw: version,
             normalized: String::new(),
+            prefixed: String::new(),
+            plain: String::new(),
             is_valid: false,
             is_prerelease: false,
         }
     }
 
-    fn validate(&mut self) -> Result<()> {
+    fn validate(&mut self, scheme: Option<Scheme>) -> Result<()> {
         let logger = get_logger(false);
         
-        // Remove 'v' prefix if present
-        let mut normalized = self.raw.trim().to_string();
-        normalized = normalized.trim_start_matches('v').to_string();
+        let parsed = self.raw.trim().parse::<Version>()
+            .and_then(|version| version.validate_release().map(|_| version));
         
-        // Add 'v' prefix back
-        normalized = format!("v{}", normalized);
-        
-        // Validate against semver pattern
-        let pattern = Regex::new(
-            r"^v(0|[1-9][0-9]*)\.(0|[1-9][0-9]*)\.(0|[1-9][0-9]*)(-beta\.[0-9]+)?$"
-        )?;
-        
-        self.is_valid = pattern.is_match(&normalized);
-        self.normalized = normalized.clone();
-        self.is_prerelease = normalized.contains("-beta.");
-
-        if self.is_valid {
-            logger.info(&format!("✅ Version '{}' is valid", self.normalized));
-        } else {
-            logger.warn(&format!("❌ Invalid version format: {}", self.raw));
-            logger.warn("Version must match pattern: v1.2.3 or v1.2.3-beta.1");
+        match parsed {
+            Ok(version) => {
+                let prefixed = version.to_scheme(Scheme::Prefixed);
+                let plain = version.to_scheme(Scheme::Plain);
+                
+                self.is_valid = true;
+                self.normalized = version.to_scheme(scheme.unwrap_or(version.scheme())).to_tag();
+                self.prefixed = prefixed.to_tag();
+                self.plain = plain.to_tag();
+                self.is_prerelease = version.is_beta();
+                
+                logger.info(&format!("✅ Version '{}' is valid", self.normalized));
+            },
+            Err(e) => {
+                self.is_valid = false;
+                self.normalized = self.raw.trim().to_string();
+                
+                logger.warn(&format!("❌ Invalid version format: {}", e));
+                logger.warn("Version must look like beta-v1.2.3, stable-v1.2.3, v1.2.3 or v1.2.3-beta.1");
+            },
         }
 
         Ok(())
@@ -58,6 +69,8 @@ impl VersionInfo {
     fn to_env_outputs(&self) -> Vec<(String, String)> {
         vec![
             ("VALIDATED_VERSION".into(), self.normalized.clone()),
+            ("PREFIXED_VERSION".into(), self.prefixed.clone()),
+            ("PLAIN_VERSION".into(), self.plain.clone()),
             ("VERSION_VALID".into(), self.is_valid.to_string()),
             ("IS_PRERELEASE".into(), self.is_prerelease.to_string()),
         ]
@@ -78,6 +91,12 @@ async fn main() -> Result<()> {
 
     logger.debug(&format!("Raw version input: {}", version));
     logger.debug(&format!("Initial version: {}", initial_version));
+    
+    // Scheme of the normalized output, the input's own scheme if unset
+    let scheme = match env::var("INPUT_SCHEME").unwrap_or_default().trim() {
+        "" | "any" => None,
+        other => Some(other.parse::<Scheme>()?),
+    };
 
     // If version is empty, use initial version
     let version_to_use = if version.is_empty() {
@@ -89,7 +108,7 @@ async fn main() -> Result<()> {
 
     // Validate version
     let mut version_info = VersionInfo::new(version_to_use);
-    version_info.validate()?;
+    version_info.validate(scheme)?;
 
     if !version_info.is_valid {
         anyhow::bail!("Invalid version format");
@@ -97,9 +116,47 @@ async fn main() -> Result<()> {
 
     // Set GitHub environment outputs
     for (key, value) in version_info.to_env_outputs() {
-        println!("::set-output name={}::{}", key, value);
+        github::set_output(&key, &value);
     }
 
     logger.info("✅ Version validation completed successfully");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(version: &str, scheme: Option<Scheme>) -> VersionInfo {
+        let mut info = VersionInfo::new(version.to_string());
+        info.validate(scheme).unwrap();
+        info
+    }
+
+    #[test]
+    fn test_accepts_both_schemes() {
+        // raw, scheme, normalized, prerelease
+        let matrix = [
+            ("beta-v1.2.3", None, "beta-v1.2.3", true),
+            ("stable-v1.2.3", None, "stable-v1.2.3", false),
+            ("v1.2.3", None, "v1.2.3", false),
+            (" 1.2.3 ", None, "v1.2.3", false),
+            ("v1.2.3-beta.4", None, "v1.2.3-beta.4", true),
+            ("beta-v1.2.3", Some(Scheme::Plain), "v1.2.3-beta", true),
+            ("stable-v1.2.3", Some(Scheme::Plain), "v1.2.3", false),
+            ("v1.2.3-beta.4", Some(Scheme::Prefixed), "beta-v1.2.3-4", true),
+            ("v1.2.3", Some(Scheme::Prefixed), "stable-v1.2.3", false),
+        ];
+
+        for (raw, scheme, normalized, prerelease) in matrix {
+            let info = validate(raw, scheme);
+            assert!(info.is_valid, "{} should be valid", raw);
+            assert_eq!(info.normalized, normalized, "normalized form of {}", raw);
+            assert_eq!(info.is_prerelease, prerelease, "prerelease flag of {}", raw);
+        }
+
+        for bad in ["", "develop-v0.0.1", "v1.2", "v1.2.3-rc.1", "v1.2.3+build.1"] {
+            assert!(!validate(bad, None).is_valid, "{} should be invalid", bad);
+        }
+    }
+}
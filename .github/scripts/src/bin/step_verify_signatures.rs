@@ -0,0 +1,82 @@
+//! Commit signature verification script for create-release action
+//! Used by: ./.github/actions/create-release/action.yml
+//! Purpose: Checks that every commit since the previous release is signed by a
+//! key in .github/trusted-signers.json, reporting unsigned and untrusted
+//! commits and, with INPUT_ENFORCE=true, blocking the release
+
+use anyhow::{bail, Context, Result};
+use github_workflow_scripts::{get_logger, init, github};
+use github_workflow_scripts::release::notes;
+use github_workflow_scripts::signing::verify::{self, SignatureStatus, TrustedSigners, TRUSTED_SIGNERS_FILE};
+use github_workflow_scripts::signing::EphemeralKeyring;
+use github_workflow_scripts::version::Version;
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init();
+    let logger = get_logger(false);
+    logger.info("🔏 Verifying commit signatures...");
+
+    let version = env::var("INPUT_VERSION").context("Missing INPUT_VERSION")?;
+    let release_sha = env::var("INPUT_RELEASE_SHA").context("Missing INPUT_RELEASE_SHA")?;
+    let enforce = env::var("INPUT_ENFORCE")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let root = env::var_os("GITHUB_WORKSPACE").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+    let config_path = env::var("INPUT_CONFIG").unwrap_or_else(|_| TRUSTED_SIGNERS_FILE.to_string());
+    let signers = TrustedSigners::load(&root.join(config_path))?;
+
+    // The release commits start after the previous release on the channel
+    let tags = Command::new("git")
+        .arg("-C")
+        .arg(&root)
+        .args(["tag", "-l"])
+        .output()
+        .context("Failed to list tags")?;
+    let tags = String::from_utf8_lossy(&tags.stdout).to_string();
+    let previous = match version.trim().parse::<Version>() {
+        Ok(current) => notes::previous_tag(tags.lines(), &current),
+        Err(_) => None,
+    };
+    logger.info(&format!("Checking commits from {} to {}", previous.unwrap_or("the first commit"), release_sha));
+
+    // Only the trusted signers' keys go into the keyring, which is removed on exit
+    let keyring = EphemeralKeyring::create()?;
+    signers.import(&root, keyring.gpg())?;
+    let commits = verify::verify_commits(&root, keyring.gpg(), &signers, previous, &release_sha)?;
+    drop(keyring);
+
+    let mut untrusted = 0;
+    for commit in &commits {
+        let problem = match &commit.status {
+            SignatureStatus::Trusted { signer } => {
+                logger.info(&format!("✅ {} signed by {}", &commit.sha[..7], signer));
+                continue;
+            },
+            SignatureStatus::Unsigned => "is not signed".to_string(),
+            SignatureStatus::Untrusted { key } => format!("is signed by untrusted key {}", key),
+            SignatureStatus::Invalid { reason } => format!("has an invalid signature: {}", reason),
+        };
+        untrusted += 1;
+        github::warning(&format!("Commit {} ({}) {}", &commit.sha[..7], commit.subject, problem));
+    }
+
+    github::set_output("commit_count", &commits.len().to_string());
+    github::set_output("untrusted_count", &untrusted.to_string());
+    github::set_output("verified", if untrusted == 0 { "true" } else { "false" });
+    github::add_step_summary(&verify::to_markdown(&commits));
+
+    if untrusted == 0 {
+        logger.info(&format!("✅ All {} commit(s) are signed by trusted keys", commits.len()));
+    } else if enforce {
+        bail!("{} of {} commit(s) are not signed by trusted keys", untrusted, commits.len());
+    } else {
+        logger.warn(&format!("{} of {} commit(s) are not signed by trusted keys; not enforced", untrusted, commits.len()));
+    }
+
+    Ok(())
+}
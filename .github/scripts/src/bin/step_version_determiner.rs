@@ -1,14 +1,33 @@
 use anyhow::{Context, Result};
-use github_workflow_scripts::{get_logger, init};
+use github_workflow_scripts::dry_run::{DryRun, PlannedAction};
+use github_workflow_scripts::{get_logger, github, init};
+use github_workflow_scripts::version::conventional::BumpDecision;
+use github_workflow_scripts::version::{Channel, Version};
 use std::{env, process::Command};
-use regex::Regex;
-use std::str::FromStr;
+
+/// How the next version number is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BumpMode {
+    /// Patch bump on beta, promote the latest beta on main
+    Fixed,
+    /// Bump according to the Conventional Commits since the last release tag
+    Conventional,
+}
+
+/// The chosen version and, in conventional mode, why
+struct NextVersion {
+    version: Version,
+    /// Bump decision and the tag the commits were scanned from
+    decision: Option<(BumpDecision, String)>,
+}
 
 /// VersionDeterminer manages the version numbering logic for releases
 struct VersionDeterminer {
     logger: Box<dyn github_workflow_scripts::Logger>,
     source_branch: String,
     default_version: String,
+    bump_mode: BumpMode,
+    dry_run: DryRun,
 }
 
 impl VersionDeterminer {
@@ -30,13 +49,22 @@ impl VersionDeterminer {
                 "beta-v0.0.1".to_string()
             });
         
-        logger.info(&format!("Initializing VersionDeterminer: branch='{}', default='{}'", 
-            &source_branch, &default_version));
+        // Choose how versions are bumped
+        let bump_mode = match env::var("INPUT_BUMP_MODE").unwrap_or_default().trim() {
+            "" | "fixed" => BumpMode::Fixed,
+            "conventional" => BumpMode::Conventional,
+            other => anyhow::bail!("Unknown INPUT_BUMP_MODE '{}': expected fixed or conventional", other),
+        };
+        
+        logger.info(&format!("Initializing VersionDeterminer: branch='{}', default='{}', mode={:?}", 
+            &source_branch, &default_version, bump_mode));
         
         Ok(Self {
             logger,
             source_branch,
             default_version,
+            bump_mode,
+            dry_run: DryRun::from_env("version_determiner"),
         })
     }
     
@@ -60,14 +88,25 @@ impl VersionDeterminer {
     }
     
     /// Fetch all tags from the repository
+    ///
+    /// A forced fetch rewrites local tags, so a dry run records it and works
+    /// with the tags already in the clone.
     fn fetch_tags(&self) -> Result<()> {
+        let args = ["fetch", "--tags", "--force"];
+        let mut fetch = Command::new("git");
+        fetch.args(args);
+        if self.dry_run.intercept_command("Fetch tags", &fetch)? {
+            return Ok(());
+        }
+        
         self.logger.info("Fetching all git tags...");
-        self.run_git_command(&["fetch", "--tags", "--force"])?;
+        self.run_git_command(&args)?;
         Ok(())
     }
     
-    /// Find the latest tag with a specific prefix
-    fn find_latest_tag_with_prefix(&self, prefix: &str) -> Result<Option<String>> {
+    /// Find the latest release version on a channel
+    fn find_latest_version(&self, channel: Channel) -> Result<Option<(Version, String)>> {
+        let prefix = channel.prefix();
         self.logger.info(&format!("Looking for tags with prefix: {}", prefix));
         
         // List all tags with the prefix
@@ -78,108 +117,59 @@ impl VersionDeterminer {
             return Ok(None);
         }
         
-        // Split into lines and sort by version
-        let mut tags: Vec<&str> = tags_output.lines().collect();
+        let tags: Vec<&str> = tags_output.lines().collect();
         
         // Log all found tags for debugging
         self.logger.debug(&format!("Found {} tags with prefix {}: {:?}", tags.len(), prefix, tags));
         
-        // Custom sort function that understands semantic versioning
-        tags.sort_by(|a, b| {
-            // Extract version parts for comparison
-            let extract_version = |tag: &str| -> Vec<i32> {
-                let re = Regex::new(&format!(r"{}-v(\d+)\.(\d+)\.(\d+)", prefix)).unwrap();
-                if let Some(caps) = re.captures(tag) {
-                    return vec![
-                        i32::from_str(&caps[1]).unwrap_or(0),
-                        i32::from_str(&caps[2]).unwrap_or(0),
-                        i32::from_str(&caps[3]).unwrap_or(0),
-                    ];
-                }
-                vec![0, 0, 0]
-            };
-            
-            let a_ver = extract_version(a);
-            let b_ver = extract_version(b);
-            
-            // Compare version components
-            for i in 0..3 {
-                let a_comp = a_ver.get(i).unwrap_or(&0);
-                let b_comp = b_ver.get(i).unwrap_or(&0);
-                
-                match a_comp.cmp(b_comp) {
-                    std::cmp::Ordering::Equal => continue,
-                    other => return other,
-                }
+        // Tags that are not valid versions are ignored rather than treated as 0.0.0
+        for tag in &tags {
+            if let Err(e) = tag.parse::<Version>() {
+                self.logger.warn(&format!("Ignoring tag {}: {}", tag, e));
             }
-            
-            std::cmp::Ordering::Equal
-        });
+        }
         
-        // Get the last (highest) tag
-        let latest_tag = tags.last().map(|&s| s.to_string());
+        let latest = Version::latest(tags, channel).map(|(version, tag)| (version, tag.to_string()));
         
-        if let Some(tag) = &latest_tag {
+        if let Some((_, tag)) = &latest {
             self.logger.info(&format!("Found latest tag with prefix {}: {}", prefix, tag));
         }
         
-        Ok(latest_tag)
+        Ok(latest)
     }
     
-    /// Extract version components from a tag
-    fn extract_version(&self, tag: &str) -> Result<String> {
-        self.logger.debug(&format!("Extracting version from tag: {}", tag));
-        
-        let re = Regex::new(r"(?:stable|beta)-v([0-9]+\.[0-9]+\.[0-9]+)").unwrap();
-        
-        if let Some(caps) = re.captures(tag) {
-            let version = caps.get(1).unwrap().as_str().to_string();
-            self.logger.debug(&format!("Extracted version: {}", version));
-            return Ok(version);
-        }
-        
-        // Try alternative pattern with just 'v'
-        let re_simple = Regex::new(r"v([0-9]+\.[0-9]+\.[0-9]+)").unwrap();
-        if let Some(caps) = re_simple.captures(tag) {
-            let version = caps.get(1).unwrap().as_str().to_string();
-            self.logger.debug(&format!("Extracted version (alt pattern): {}", version));
-            return Ok(version);
-        }
-        
-        self.logger.warn(&format!("Could not extract version from tag: {}", tag));
-        Ok("0.0.1".to_string())  // Default if no match
+    /// Commits after `tag` up to HEAD as `(sha, message)` pairs, oldest first
+    fn commits_since(&self, tag: &str) -> Result<Vec<(String, String)>> {
+        let log = self.run_git_command(&["log", "--reverse", "--format=%H%x1f%B%x1e", &format!("{}..HEAD", tag)])?;
+        
+        Ok(log
+            .split('\x1e')
+            .filter_map(|record| record.trim().split_once('\x1f'))
+            .map(|(sha, message)| (sha.to_string(), message.trim().to_string()))
+            .collect())
     }
     
-    /// Increment a specific component of a version string
-    fn increment_version(&self, version: &str, position: usize) -> Result<String> {
-        self.logger.debug(&format!("Incrementing version {} at position {}", version, position));
-        
-        // Parse the version into components
-        let mut parts: Vec<i32> = version
-            .split('.')
-            .map(|s| i32::from_str(s).unwrap_or(0))
-            .collect();
-            
-        // Ensure we have at least 3 parts
-        while parts.len() < 3 {
-            parts.push(0);
-        }
-        
-        // Increment the specified position (0-indexed in the function)
-        if position <= parts.len() {
-            parts[position - 1] += 1;
-            
-            // Reset subsequent components
-            for i in position..parts.len() {
-                parts[i] = 0;
-            }
-        }
+    /// Bump `base` according to the Conventional Commits since its tag
+    fn determine_conventional_version(&self, channel: Channel, base: Option<&(Version, String)>) -> Result<NextVersion> {
+        let Some((base, tag)) = base else {
+            let version = self.initial_version(channel)?;
+            self.logger.info(&format!("No previous release tag, using initial version: {}", version));
+            return Ok(NextVersion { version, decision: None });
+        };
         
-        // Rebuild the version string
-        let new_version = parts.iter().map(|&n| n.to_string()).collect::<Vec<String>>().join(".");
-        self.logger.debug(&format!("Incremented version: {}", new_version));
+        let commits = self.commits_since(tag)?;
+        let decision = BumpDecision::from_commits(commits.iter().map(|(sha, message)| (sha.as_str(), message.as_str())));
+        self.logger.info(&format!("Commits since {}: {}", tag, decision.reasoning().trim_end()));
         
-        Ok(new_version)
+        let version = decision.bump.apply(&base.with_channel(Some(channel)));
+        Ok(NextVersion { version, decision: Some((decision, tag.clone())) })
+    }
+    
+    /// Parse the configured initial version onto a channel
+    fn initial_version(&self, channel: Channel) -> Result<Version> {
+        let version: Version = self.default_version.parse()
+            .context("INPUT_INITIAL_VERSION is not a valid version")?;
+        Ok(version.with_channel(Some(channel)))
     }
     
     fn get_normalized_branch(&self) -> Result<String> {
@@ -201,7 +191,7 @@ impl VersionDeterminer {
     }
     
     /// Determine the next version based on branch and existing tags
-    async fn determine_version(&self) -> Result<(String, bool)> {
+    async fn determine_version(&self) -> Result<NextVersion> {
         // Get normalized branch with strict validation
         let normalized_branch = self.get_normalized_branch()?;
         self.logger.info(&format!("Determining version for branch: '{}'", normalized_branch));
@@ -209,53 +199,57 @@ impl VersionDeterminer {
         // Make sure we have all tags
         self.fetch_tags()?;
         
-        // Find latest tags for each prefix
-        let latest_beta_tag = self.find_latest_tag_with_prefix("beta-v")?;
-        let latest_stable_tag = self.find_latest_tag_with_prefix("stable-v")?;
+        // Find latest versions for each channel
+        let latest_beta = self.find_latest_version(Channel::Beta)?;
+        let latest_stable = self.find_latest_version(Channel::Stable)?;
         
-        self.logger.info(&format!("Latest beta tag: {:?}", latest_beta_tag));
-        self.logger.info(&format!("Latest stable tag: {:?}", latest_stable_tag));
+        self.logger.info(&format!("Latest beta version: {:?}", latest_beta.as_ref().map(|(_, tag)| tag)));
+        self.logger.info(&format!("Latest stable version: {:?}", latest_stable.as_ref().map(|(_, tag)| tag)));
+        
+        if self.bump_mode == BumpMode::Conventional {
+            // Beta continues from the latest beta, or from stable before the first beta
+            let next = if normalized_branch == "beta" {
+                self.determine_conventional_version(Channel::Beta, latest_beta.as_ref().or(latest_stable.as_ref()))?
+            } else {
+                self.determine_conventional_version(Channel::Stable, latest_stable.as_ref())?
+            };
+            self.logger.info(&format!("Determined new version: {} (is_beta: {})", next.version, next.version.is_beta()));
+            return Ok(next);
+        }
         
         // Determine the next version based on branch and tags
         // Since we've validated the branch above, we know it's either "main" or "beta"
-        let (new_version, is_beta) = if normalized_branch == "beta" {
+        let new_version = if normalized_branch == "beta" {
             self.logger.info("Using beta branch versioning logic");
-            if let Some(tag) = &latest_beta_tag {
+            if let Some((version, _)) = &latest_beta {
                 // Increment patch version of latest beta
-                let version = self.extract_version(tag)?;
-                let incremented = self.increment_version(&version, 3)?;
-                (format!("beta-v{}", incremented), true)
-            } else if let Some(tag) = &latest_stable_tag {
+                version.bump_patch()
+            } else if let Some((version, _)) = &latest_stable {
                 // Derive from stable by incrementing minor
-                let version = self.extract_version(tag)?;
-                let incremented = self.increment_version(&version, 2)?;
-                (format!("beta-v{}", incremented), true)
+                version.bump_minor().with_channel(Some(Channel::Beta))
             } else {
                 // No tags at all
-                (self.default_version.clone(), true)
+                self.initial_version(Channel::Beta)?
             }
         } else {
             // This must be main branch due to our validation
             self.logger.info("Using main branch versioning logic");
-            if let Some(tag) = &latest_beta_tag {
-                // Promote beta to stable
-                let version = self.extract_version(tag)?;
-                (format!("stable-v{}", version), false)
-            } else if let Some(tag) = &latest_stable_tag {
+            if let Some((version, _)) = &latest_beta {
+                // Promote beta to stable, dropping any pre-release identifiers
+                Version::new(Some(Channel::Stable), version.major, version.minor, version.patch)
+            } else if let Some((version, _)) = &latest_stable {
                 // Increment patch version of latest stable
-                let version = self.extract_version(tag)?;
-                let incremented = self.increment_version(&version, 3)?;
-                (format!("stable-v{}", incremented), false)
+                version.bump_patch()
             } else {
                 // No tags at all
-                let default_stable = self.default_version.replace("beta-v", "stable-v");
+                let default_stable = self.initial_version(Channel::Stable)?;
                 self.logger.info(&format!("No tags found, using default stable version: {}", default_stable));
-                (default_stable, false)
+                default_stable
             }
         };
         
-        self.logger.info(&format!("Determined new version: {} (is_beta: {})", &new_version, is_beta));
-        Ok((new_version, is_beta))
+        self.logger.info(&format!("Determined new version: {} (is_beta: {})", new_version, new_version.is_beta()));
+        Ok(NextVersion { version: new_version, decision: None })
     }
     
     /// Execute the version determination process
@@ -263,11 +257,25 @@ impl VersionDeterminer {
         self.logger.info("Starting version determiner execution");
         
         // Get the new version and beta status
-        let (version, is_beta) = self.determine_version().await?;
+        let next = self.determine_version().await?;
+        let version = next.version.to_string();
+        let is_beta = next.version.is_beta();
+        
+        // Set outputs for GitHub Actions; a dry run also records them in the plan
+        let mut outputs = vec![("version", version.clone()), ("is_beta", is_beta.to_string())];
+        if let Some((decision, _)) = &next.decision {
+            outputs.push(("bump", decision.bump.to_string()));
+        }
+        for (name, value) in outputs {
+            github::set_output(name, &value);
+            self.dry_run.record("Set output", PlannedAction::Output { name: name.to_string(), value })?;
+        }
         
-        // Set outputs for GitHub Actions
-        github_workflow_scripts::github::set_output("version", &version);
-        github_workflow_scripts::github::set_output("is_beta", if is_beta { "true" } else { "false" });
+        // Explain conventional bumps in the outputs and the job summary
+        if let Some((decision, tag)) = &next.decision {
+            github::set_multiline_output("bump_reason", &decision.reasoning());
+            github::add_step_summary(&decision.to_markdown(tag, &next.version));
+        }
         
         self.logger.info(&format!("Successfully determined version: {} (beta: {})", version, is_beta));
         Ok(())
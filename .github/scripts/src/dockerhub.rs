@@ -0,0 +1,114 @@
+//! Docker Hub repository tags through the Hub API
+//!
+//! Pushing goes through the docker CLI, but the CLI cannot delete a tag from a
+//! registry. [`DockerHubClient`] logs in with the account's username and access
+//! token and removes tags through the Hub's REST API instead.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+/// Default Hub API root
+pub const HUB_URL: &str = "https://hub.docker.com";
+
+#[derive(Debug, Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+/// Authenticated Docker Hub API client
+pub struct DockerHubClient {
+    http: reqwest::Client,
+    api_url: String,
+    token: String,
+}
+
+impl DockerHubClient {
+    /// Log in to hub.docker.com
+    pub async fn login(username: &str, password: &str) -> Result<Self> {
+        Self::login_at(HUB_URL, username, password).await
+    }
+
+    /// Log in against another API root, such as a test server
+    pub async fn login_at(api_url: &str, username: &str, password: &str) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent("github-workflow-scripts")
+            .build()
+            .context("Failed to build HTTP client")?;
+        let api_url = api_url.trim_end_matches('/').to_string();
+
+        let response = http
+            .post(format!("{}/v2/users/login", api_url))
+            .json(&json!({ "username": username, "password": password }))
+            .send()
+            .await
+            .context("Failed to log in to Docker Hub")?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("Docker Hub login as {} failed: {}", username, status);
+        }
+        let login: LoginResponse = response.json().await.context("Failed to parse Docker Hub login response")?;
+
+        Ok(Self { http, api_url, token: login.token })
+    }
+
+    /// API route of a tag, e.g. `/v2/repositories/owner/image/tags/beta-v1.2.3/`
+    pub fn tag_route(repository: &str, tag: &str) -> String {
+        format!("/v2/repositories/{}/tags/{}/", repository, tag)
+    }
+
+    /// Delete `tag` from `repository` (`owner/image`); returns false if it did not exist
+    pub async fn delete_tag(&self, repository: &str, tag: &str) -> Result<bool> {
+        let response = self.http
+            .delete(format!("{}{}", self.api_url, Self::tag_route(repository, tag)))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete {}:{}", repository, tag))?;
+
+        let status = response.status();
+        if status.as_u16() == 404 {
+            return Ok(false);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("Failed to delete {}:{}: {} {}", repository, tag, status, body);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_deletes_tags_after_login() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v2/users/login"))
+            .and(body_partial_json(json!({ "username": "bot" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "token": "jwt" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v2/repositories/acme/env/tags/beta-v1.2.3/"))
+            .and(header("authorization", "Bearer jwt"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v2/repositories/acme/env/tags/beta-v0.0.1/"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = DockerHubClient::login_at(&server.uri(), "bot", "secret").await.unwrap();
+        assert!(client.delete_tag("acme/env", "beta-v1.2.3").await.unwrap());
+        assert!(!client.delete_tag("acme/env", "beta-v0.0.1").await.unwrap());
+    }
+}
@@ -0,0 +1,264 @@
+//! Version stamps in the documentation
+//!
+//! Which files mention the released version, and where, is configured in
+//! `.github/docs-version.json`:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     {
+//!       "files": ["docs/**/*.md"],
+//!       "pattern": "<!-- VERSION -->(?P<version>[^<]+)<!-- /VERSION -->",
+//!       "format": "plain"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Each rule's `pattern` is a regex whose `version` group (or first capture
+//! group, if there is no `version` group) holds the current version; only
+//! that group is replaced. `format` picks how the new version is written:
+//! `tag` as given (the default), `plain` in the plain scheme (`v1.2.3`,
+//! `v1.2.3-beta.4`) or `core` as bare `1.2.3`. Every glob must match at least
+//! one file and every rule at least one occurrence, so a stale configuration
+//! fails instead of silently stamping nothing.
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::version::{Scheme, Version};
+
+/// Default location of the stamp configuration, relative to the repository root
+pub const CONFIG_FILE: &str = ".github/docs-version.json";
+
+/// How a rule writes the new version
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionFormat {
+    /// The version as released, e.g. `beta-v1.2.3`
+    #[default]
+    Tag,
+    /// The plain scheme, e.g. `v1.2.3-beta.4`
+    Plain,
+    /// The numeric core, e.g. `1.2.3`
+    Core,
+}
+
+impl VersionFormat {
+    pub fn render(&self, version: &Version) -> String {
+        match self {
+            VersionFormat::Tag => version.to_tag(),
+            VersionFormat::Plain => version.to_scheme(Scheme::Plain).to_tag(),
+            VersionFormat::Core => version.core(),
+        }
+    }
+}
+
+/// Files and the pattern of the version in them
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StampRule {
+    /// Glob patterns relative to the repository root
+    pub files: Vec<String>,
+    pub pattern: String,
+    #[serde(default)]
+    pub format: VersionFormat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StampConfig {
+    pub rules: Vec<StampRule>,
+}
+
+impl StampConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+/// A file whose version references are out of date
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleFile {
+    /// Path relative to the repository root
+    pub path: PathBuf,
+    /// `(old, new)` for each replaced occurrence
+    pub replacements: Vec<(String, String)>,
+    /// File contents with every occurrence stamped
+    pub contents: String,
+}
+
+struct CompiledRule {
+    files: Vec<String>,
+    regex: Regex,
+    group: usize,
+    format: VersionFormat,
+}
+
+/// Finds and rewrites the configured version references under a repository root
+pub struct Stamper {
+    root: PathBuf,
+    rules: Vec<CompiledRule>,
+}
+
+impl Stamper {
+    pub fn new(root: impl Into<PathBuf>, config: &StampConfig) -> Result<Self> {
+        if config.rules.is_empty() {
+            bail!("The docs version configuration has no rules");
+        }
+
+        let rules = config.rules
+            .iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern)
+                    .with_context(|| format!("Invalid version pattern '{}'", rule.pattern))?;
+                let names: Vec<Option<&str>> = regex.capture_names().collect();
+                let group = match names.iter().position(|name| *name == Some("version")) {
+                    Some(group) => group,
+                    None if names.len() > 1 => 1,
+                    None => bail!("Version pattern '{}' has no capture group", rule.pattern),
+                };
+                Ok(CompiledRule { files: rule.files.clone(), regex, group, format: rule.format })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { root: root.into(), rules })
+    }
+
+    /// Files that do not mention `version` yet, with their stamped contents
+    pub fn stale_files(&self, version: &Version) -> Result<Vec<StaleFile>> {
+        // Several rules may touch the same file; apply them in order to one copy
+        let mut files: BTreeMap<PathBuf, (String, Vec<(String, String)>)> = BTreeMap::new();
+
+        for rule in &self.rules {
+            let new = rule.format.render(version);
+            let mut occurrences = 0;
+
+            for path in self.expand(&rule.files)? {
+                if !files.contains_key(&path) {
+                    let contents = fs::read_to_string(self.root.join(&path))
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    files.insert(path.clone(), (contents, Vec::new()));
+                }
+                let (contents, replacements) = files.get_mut(&path).expect("file was just read");
+
+                let mut stamped = String::with_capacity(contents.len());
+                let mut last = 0;
+                for captures in rule.regex.captures_iter(contents) {
+                    let Some(old) = captures.get(rule.group) else {
+                        continue;
+                    };
+                    occurrences += 1;
+                    if old.as_str() != new {
+                        replacements.push((old.as_str().to_string(), new.clone()));
+                    }
+                    stamped.push_str(&contents[last..old.start()]);
+                    stamped.push_str(&new);
+                    last = old.end();
+                }
+                stamped.push_str(&contents[last..]);
+                *contents = stamped;
+            }
+
+            if occurrences == 0 {
+                bail!("Version pattern '{}' matched nothing in {}", rule.regex, rule.files.join(", "));
+            }
+        }
+
+        Ok(files
+            .into_iter()
+            .filter(|(_, (_, replacements))| !replacements.is_empty())
+            .map(|(path, (contents, replacements))| StaleFile { path, replacements, contents })
+            .collect())
+    }
+
+    /// Write the stamped contents of `file`
+    pub fn write(&self, file: &StaleFile) -> Result<()> {
+        let path = self.root.join(&file.path);
+        fs::write(&path, &file.contents)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Files matched by `patterns`, relative to the root and sorted
+    fn expand(&self, patterns: &[String]) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for pattern in patterns {
+            let full = self.root.join(pattern);
+            let full = full.to_str().ok_or_else(|| anyhow!("Invalid file pattern {}", pattern))?;
+            let before = paths.len();
+
+            for entry in glob::glob(full).with_context(|| format!("Invalid file pattern {}", pattern))? {
+                let path = entry.with_context(|| format!("Failed to read files matching {}", pattern))?;
+                if path.is_file() {
+                    paths.push(path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf());
+                }
+            }
+            if paths.len() == before {
+                bail!("File pattern {} matched no files", pattern);
+            }
+        }
+
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> StampConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_stamps_every_match_in_matching_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("docs/guide")).unwrap();
+        fs::write(dir.path().join("README.md"), "Early development (v0.0.1)\nStill (v0.0.1)\n").unwrap();
+        fs::write(dir.path().join("docs/guide/install.md"), "VERSION=0.0.1 and VERSION=1.2.3\n").unwrap();
+        fs::write(dir.path().join("docs/current.md"), "<!-- VERSION -->v1.2.3-beta.4<!-- /VERSION -->\n").unwrap();
+
+        let stamper = Stamper::new(dir.path(), &config(r#"{"rules": [
+            {"files": ["README.md"], "pattern": "development \\((v[^)]+)\\)|Still \\((v[^)]+)\\)", "format": "plain"},
+            {"files": ["docs/**/*.md"], "pattern": "VERSION=(?P<version>[0-9.]+)", "format": "core"},
+            {"files": ["docs/*.md"], "pattern": "<!-- VERSION -->(?P<version>[^<]+)<!--", "format": "plain"}
+        ]}"#)).unwrap();
+
+        let version: Version = "beta-v1.2.3-4".parse().unwrap();
+        let stale = stamper.stale_files(&version).unwrap();
+        let paths: Vec<&Path> = stale.iter().map(|file| file.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("README.md"), Path::new("docs/guide/install.md")]);
+
+        // Only the first group of an alternation counts when there is no `version` group
+        assert_eq!(stale[0].replacements, [("v0.0.1".to_string(), "v1.2.3-beta.4".to_string())]);
+        assert_eq!(stale[0].contents, "Early development (v1.2.3-beta.4)\nStill (v0.0.1)\n");
+        assert_eq!(stale[1].contents, "VERSION=1.2.3 and VERSION=1.2.3\n");
+
+        for file in &stale {
+            stamper.write(file).unwrap();
+        }
+        assert!(stamper.stale_files(&version).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_stale_configuration() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("README.md"), "no version here\n").unwrap();
+        let version: Version = "v1.0.0".parse().unwrap();
+
+        let missing = Stamper::new(dir.path(), &config(r#"{"rules": [{"files": ["docs/*.md"], "pattern": "v(\\d+)"}]}"#)).unwrap();
+        assert!(missing.stale_files(&version).unwrap_err().to_string().contains("matched no files"));
+
+        let unmatched = Stamper::new(dir.path(), &config(r#"{"rules": [{"files": ["README.md"], "pattern": "(v\\d+\\.\\d+\\.\\d+)"}]}"#)).unwrap();
+        assert!(unmatched.stale_files(&version).unwrap_err().to_string().contains("matched nothing"));
+
+        assert!(Stamper::new(dir.path(), &config(r#"{"rules": [{"files": ["README.md"], "pattern": "v1"}]}"#)).is_err());
+    }
+}
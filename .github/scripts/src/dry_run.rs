@@ -0,0 +1,211 @@
+//! Dry-run (plan) mode for the release binaries
+//!
+//! With `DRY_RUN=true` the release steps still read everything they need
+//! (tags, files, the queue) but do not change anything. Each side effect they
+//! would have performed is logged and recorded as a [`PlannedAction`] in a
+//! JSON plan, `release-plan.json` in the workspace unless `DRY_RUN_PLAN`
+//! points elsewhere. Steps of one job append to the same plan, so the file
+//! describes the whole release once the job is done.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{log, LogLevel};
+
+/// Environment variable that turns dry-run mode on
+pub const DRY_RUN_ENV: &str = "DRY_RUN";
+/// Environment variable with the path of the plan file
+pub const PLAN_ENV: &str = "DRY_RUN_PLAN";
+/// Default plan file name
+pub const PLAN_FILE: &str = "release-plan.json";
+
+/// A side effect a release step would have performed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlannedAction {
+    /// An external program, e.g. `git push` or `docker build`
+    Command {
+        program: String,
+        args: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+    },
+    /// A GitHub REST API request
+    Api { method: String, route: String },
+    /// A file that would be created or overwritten
+    WriteFile { path: String },
+    /// A file or directory that would be deleted
+    RemovePath { path: String },
+    /// A step output, recorded so the plan shows the values later steps would see
+    Output { name: String, value: String },
+}
+
+impl PlannedAction {
+    /// Describe a command without running it
+    pub fn command(command: &Command) -> Self {
+        PlannedAction::Command {
+            program: command.get_program().to_string_lossy().into_owned(),
+            args: command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect(),
+            cwd: command.get_current_dir().map(|dir| dir.display().to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for PlannedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlannedAction::Command { program, args, cwd } => {
+                write!(f, "{} {}", program, args.join(" "))?;
+                if let Some(cwd) = cwd {
+                    write!(f, " (in {})", cwd)?;
+                }
+                Ok(())
+            },
+            PlannedAction::Api { method, route } => write!(f, "{} {}", method, route),
+            PlannedAction::WriteFile { path } => write!(f, "write {}", path),
+            PlannedAction::RemovePath { path } => write!(f, "remove {}", path),
+            PlannedAction::Output { name, value } => write!(f, "output {}={}", name, value),
+        }
+    }
+}
+
+/// One recorded action, tagged with the step that planned it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanEntry {
+    pub step: String,
+    pub description: String,
+    pub action: PlannedAction,
+    pub at: DateTime<Utc>,
+}
+
+/// The JSON plan file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub entries: Vec<PlanEntry>,
+}
+
+impl Plan {
+    /// Read a plan; a missing file is an empty plan
+    pub fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Gate for the side effects of one release step
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    step: String,
+    plan_path: Option<PathBuf>,
+}
+
+impl DryRun {
+    /// Read `DRY_RUN` and `DRY_RUN_PLAN` for the step named `step`
+    pub fn from_env(step: &str) -> Self {
+        let enabled = std::env::var(DRY_RUN_ENV)
+            .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        if !enabled {
+            return Self::disabled(step);
+        }
+
+        let path = std::env::var_os(PLAN_ENV).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(PLAN_FILE));
+        let path = match std::env::var_os("GITHUB_WORKSPACE") {
+            Some(workspace) if path.is_relative() => Path::new(&workspace).join(path),
+            _ => path,
+        };
+        Self::with_plan(step, path)
+    }
+
+    /// Perform side effects normally
+    pub fn disabled(step: &str) -> Self {
+        Self { step: step.to_string(), plan_path: None }
+    }
+
+    /// Record side effects in the plan at `path` instead of performing them
+    pub fn with_plan(step: &str, path: impl Into<PathBuf>) -> Self {
+        Self { step: step.to_string(), plan_path: Some(path.into()) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.plan_path.is_some()
+    }
+
+    pub fn plan_path(&self) -> Option<&Path> {
+        self.plan_path.as_deref()
+    }
+
+    /// Add `action` to the plan when dry-running; does nothing otherwise
+    pub fn record(&self, description: &str, action: PlannedAction) -> Result<()> {
+        let Some(path) = &self.plan_path else {
+            return Ok(());
+        };
+
+        log(LogLevel::Info, &format!("[dry-run] {}: {}", description, action));
+
+        let mut plan = Plan::read(path)?;
+        plan.entries.push(PlanEntry {
+            step: self.step.clone(),
+            description: description.to_string(),
+            action,
+            at: Utc::now(),
+        });
+        plan.write(path)
+    }
+
+    /// Record `action` if dry-running; returns whether the caller must skip it
+    pub fn intercept(&self, description: &str, action: PlannedAction) -> Result<bool> {
+        self.record(description, action)?;
+        Ok(self.is_enabled())
+    }
+
+    /// Record `command` if dry-running; returns whether the caller must skip it
+    pub fn intercept_command(&self, description: &str, command: &Command) -> Result<bool> {
+        self.intercept(description, PlannedAction::command(command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_actions_across_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PLAN_FILE);
+
+        assert!(!DryRun::disabled("build").intercept("Write file", PlannedAction::WriteFile { path: "x".into() }).unwrap());
+        assert!(!path.exists());
+
+        let mut push = Command::new("git");
+        push.args(["push", "origin", "refs/tags/beta-v1.2.3"]).current_dir("/repo");
+        assert!(DryRun::with_plan("create_release", &path).intercept_command("Push tag", &push).unwrap());
+        let output = PlannedAction::Output { name: "version".into(), value: "beta-v1.2.3".into() };
+        DryRun::with_plan("version_determiner", &path).record("Set output", output.clone()).unwrap();
+
+        let plan = Plan::read(&path).unwrap();
+        assert_eq!(plan.entries.len(), 2);
+        assert_eq!(plan.entries[0].step, "create_release");
+        assert_eq!(plan.entries[0].action.to_string(), "git push origin refs/tags/beta-v1.2.3 (in /repo)");
+        assert_eq!(plan.entries[1].action, output);
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["entries"][0]["action"]["kind"], "command");
+        assert_eq!(json["entries"][0]["action"]["args"][0], "push");
+    }
+}
@@ -29,7 +29,6 @@ pub fn set_output(name: &str, value: &str) {
     if let Ok(path) = std::env::var("GITHUB_OUTPUT") {
         // Use GitHub Actions output file approach (preferred)
         let mut file = std::fs::OpenOptions::new()
-            .write(true)
             .append(true)
             .open(path)
             .unwrap_or_else(|_| panic!("Failed to open GITHUB_OUTPUT file"));
@@ -45,6 +44,60 @@ pub fn set_output(name: &str, value: &str) {
     }
 }
 
+/// Sets a multi-line output parameter for GitHub Actions
+///
+/// Uses the heredoc form of the GITHUB_OUTPUT file with a delimiter that does
+/// not occur in the value. Without GITHUB_OUTPUT the value is printed as-is.
+///
+/// # Arguments
+///
+/// * `name` - The name of the output parameter
+/// * `value` - The value, which may span several lines
+pub fn set_multiline_output(name: &str, value: &str) {
+    if let Ok(path) = std::env::var("GITHUB_OUTPUT") {
+        let mut delimiter = format!("ghadelimiter_{}", std::process::id());
+        while value.contains(&delimiter) {
+            delimiter.push('_');
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|_| panic!("Failed to open GITHUB_OUTPUT file"));
+
+        writeln!(file, "{}<<{}\n{}\n{}", name, delimiter, value.trim_end_matches('\n'), delimiter)
+            .unwrap_or_else(|_| panic!("Failed to write to GITHUB_OUTPUT file"));
+
+        log(LogLevel::Debug, &format!("Setting multi-line output parameter {}", name));
+    } else {
+        println!("{}:\n{}", name, value);
+    }
+}
+
+/// Appends Markdown to the job summary shown on the workflow run page
+///
+/// Writes to the GITHUB_STEP_SUMMARY file; outside of Actions the Markdown is printed instead.
+///
+/// # Arguments
+///
+/// * `markdown` - The Markdown to append
+pub fn add_step_summary(markdown: &str) {
+    if let Ok(path) = std::env::var("GITHUB_STEP_SUMMARY") {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|_| panic!("Failed to open GITHUB_STEP_SUMMARY file"));
+
+        writeln!(file, "{}", markdown)
+            .unwrap_or_else(|_| panic!("Failed to write to GITHUB_STEP_SUMMARY file"));
+
+        log(LogLevel::Debug, "Appended to the job summary");
+    } else {
+        println!("{}", markdown);
+    }
+}
+
 /// Sets an environment variable for the current and future steps in a workflow
 ///
 /// # Arguments
@@ -55,7 +108,6 @@ pub fn set_env(name: &str, value: &str) {
     if let Ok(path) = std::env::var("GITHUB_ENV") {
         // Use GitHub Actions environment file approach (preferred)
         let mut file = std::fs::OpenOptions::new()
-            .write(true)
             .append(true)
             .open(path)
             .unwrap_or_else(|_| panic!("Failed to open GITHUB_ENV file"));
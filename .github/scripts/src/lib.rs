@@ -6,6 +6,16 @@
 // Re-export modules for ease of use
 pub mod logger;
 pub mod github;
+pub mod dockerhub;
+pub mod docs;
+pub mod dry_run;
+pub mod release;
+pub mod release_queue;
+pub mod signing;
+pub mod version;
+
+#[cfg(test)]
+mod test_support;
 
 use chrono::Utc;
 
@@ -0,0 +1,385 @@
+//! GitHub releases through the REST API
+//!
+//! [`ReleaseClient`] creates the release for a tag and attaches the packaged
+//! assets. Both steps are idempotent: an existing release for the tag is
+//! reused and an asset already attached under the same name is replaced, so a
+//! re-run after a partial failure finishes the release instead of erroring.
+//!
+//! Releases are read and created through octocrab. Asset uploads go through
+//! reqwest because octocrab only sends JSON bodies, while an upload is the raw
+//! file posted to the `upload_url` host the release reports.
+//!
+//! [`manifest`] describes a published release for installers and [`notes`]
+//! writes its release notes. [`promotion`] checks a beta release before it is
+//! promoted to stable.
+
+pub mod manifest;
+pub mod notes;
+pub mod promotion;
+
+use anyhow::{anyhow, bail, Context, Result};
+use octocrab::{FromResponse, Octocrab};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use notes::PullRequest;
+
+/// Default REST API root
+pub const API_URL: &str = "https://api.github.com";
+
+/// A release as returned by the API, reduced to the fields the scripts use
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    pub id: u64,
+    pub tag_name: String,
+    pub html_url: String,
+    /// Upload endpoint as a URI template, e.g. `.../assets{?name,label}`
+    pub upload_url: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    /// The attached asset called `name`
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// Upload endpoint without the URI template suffix
+    fn upload_endpoint(&self) -> &str {
+        self.upload_url.split('{').next().unwrap_or(&self.upload_url)
+    }
+}
+
+/// A file attached to a release
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReleaseAsset {
+    pub id: u64,
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
+}
+
+/// Request body for creating a release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NewRelease {
+    pub tag_name: String,
+    pub target_commitish: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    /// Let GitHub append its generated notes to `body`
+    pub generate_release_notes: bool,
+}
+
+/// Release operations for one repository
+pub struct ReleaseClient {
+    octocrab: Octocrab,
+    http: reqwest::Client,
+    api_url: String,
+    token: String,
+    owner: String,
+    repo: String,
+}
+
+impl ReleaseClient {
+    /// Client for `repository` (`owner/repo`) on github.com
+    pub fn new(token: &str, repository: &str) -> Result<Self> {
+        Self::with_api_url(token, repository, API_URL)
+    }
+
+    /// Client against another API root, such as GitHub Enterprise or a test server
+    pub fn with_api_url(token: &str, repository: &str, api_url: &str) -> Result<Self> {
+        let (owner, repo) = repository
+            .split_once('/')
+            .filter(|(owner, repo)| !owner.is_empty() && !repo.is_empty())
+            .ok_or_else(|| anyhow!("Invalid repository '{}': expected owner/repo", repository))?;
+
+        let octocrab = Octocrab::builder()
+            .personal_token(token.to_string())
+            .base_uri(api_url)
+            .context("Invalid GitHub API URL")?
+            .build()
+            .context("Failed to build Octocrab instance")?;
+        let http = reqwest::Client::builder()
+            .user_agent("github-workflow-scripts")
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            octocrab,
+            http,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        })
+    }
+
+    /// API route of the release list
+    pub fn releases_route(&self) -> String {
+        format!("/repos/{}/{}/releases", self.owner, self.repo)
+    }
+
+    /// The release for `tag`, if there is one
+    pub async fn find_by_tag(&self, tag: &str) -> Result<Option<Release>> {
+        let route = format!("{}/tags/{}", self.releases_route(), tag);
+        let response = self.octocrab
+            ._get(route.as_str())
+            .await
+            .with_context(|| format!("Failed to look up release {}", tag))?;
+
+        if response.status().as_u16() == 404 {
+            return Ok(None);
+        }
+        let response = octocrab::map_github_error(response)
+            .await
+            .with_context(|| format!("Failed to look up release {}", tag))?;
+        Ok(Some(Release::from_response(response).await?))
+    }
+
+    /// Pull request `number`, if it exists
+    pub async fn pull_request(&self, number: u64) -> Result<Option<PullRequest>> {
+        let route = format!("/repos/{}/{}/pulls/{}", self.owner, self.repo, number);
+        let response = self.octocrab
+            ._get(route.as_str())
+            .await
+            .with_context(|| format!("Failed to look up pull request #{}", number))?;
+
+        if response.status().as_u16() == 404 {
+            return Ok(None);
+        }
+        let response = octocrab::map_github_error(response)
+            .await
+            .with_context(|| format!("Failed to look up pull request #{}", number))?;
+        Ok(Some(PullRequest::from_response(response).await?))
+    }
+
+    /// Create the release unless its tag already has one
+    ///
+    /// Returns the release and whether this call created it.
+    pub async fn create_or_get(&self, release: &NewRelease) -> Result<(Release, bool)> {
+        if let Some(existing) = self.find_by_tag(&release.tag_name).await? {
+            return Ok((existing, false));
+        }
+
+        let created: Release = self.octocrab
+            .post(self.releases_route(), Some(release))
+            .await
+            .with_context(|| format!("Failed to create release {}", release.tag_name))?;
+        Ok((created, true))
+    }
+
+    /// Delete a release; its tag is left in place
+    pub async fn delete(&self, release: &Release) -> Result<()> {
+        let route = format!("{}/{}", self.releases_route(), release.id);
+        let response = self.octocrab
+            ._delete(route.as_str(), None::<&()>)
+            .await
+            .with_context(|| format!("Failed to delete release {}", release.tag_name))?;
+        octocrab::map_github_error(response)
+            .await
+            .with_context(|| format!("Failed to delete release {}", release.tag_name))?;
+        Ok(())
+    }
+
+    /// Contents of an attached asset
+    pub async fn download_asset(&self, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+        let url = format!("{}{}/assets/{}", self.api_url, self.releases_route(), asset.id);
+        let response = self.http
+            .get(url)
+            .bearer_auth(&self.token)
+            .header(ACCEPT, "application/octet-stream")
+            .send()
+            .await
+            .with_context(|| format!("Failed to download {}", asset.name))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            bail!("Failed to download {}: {}", asset.name, status);
+        }
+        Ok(response.bytes().await.with_context(|| format!("Failed to download {}", asset.name))?.to_vec())
+    }
+
+    /// Attach the file at `path`, replacing an asset with the same name
+    pub async fn upload_asset(&self, release: &Release, path: &Path) -> Result<ReleaseAsset> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("Invalid asset path {}", path.display()))?;
+
+        // GitHub rejects a second asset with the same name
+        if let Some(existing) = release.asset(name) {
+            let route = format!("{}/assets/{}", self.releases_route(), existing.id);
+            let response = self.octocrab
+                ._delete(route.as_str(), None::<&()>)
+                .await
+                .with_context(|| format!("Failed to delete existing asset {}", name))?;
+            octocrab::map_github_error(response)
+                .await
+                .with_context(|| format!("Failed to delete existing asset {}", name))?;
+        }
+
+        let contents = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let response = self.http
+            .post(release.upload_endpoint())
+            .query(&[("name", name)])
+            .bearer_auth(&self.token)
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(CONTENT_TYPE, content_type(name))
+            .body(contents)
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload {}", name))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("Failed to upload {}: {} {}", name, status, body);
+        }
+        response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse upload response for {}", name))
+    }
+}
+
+/// Content type sent with an uploaded asset
+fn content_type(name: &str) -> &'static str {
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        "application/gzip"
+    } else if name.ends_with(".json") {
+        "application/json"
+    } else if name.ends_with(".txt") {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn release_json(server: &MockServer, assets: serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": 7,
+            "tag_name": "beta-v1.2.3",
+            "html_url": "https://github.com/acme/env/releases/tag/beta-v1.2.3",
+            "upload_url": format!("{}/uploads/releases/7/assets{{?name,label}}", server.uri()),
+            "prerelease": true,
+            "assets": assets,
+        })
+    }
+
+    fn asset_json(id: u64, name: &str, size: u64) -> serde_json::Value {
+        json!({
+            "id": id,
+            "name": name,
+            "size": size,
+            "browser_download_url": format!("https://github.com/acme/env/releases/download/beta-v1.2.3/{}", name),
+        })
+    }
+
+    fn new_release() -> NewRelease {
+        NewRelease {
+            tag_name: "beta-v1.2.3".to_string(),
+            target_commitish: "a".repeat(40),
+            name: "Beta Release beta-v1.2.3".to_string(),
+            body: None,
+            draft: false,
+            prerelease: true,
+            generate_release_notes: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_creates_release_and_uploads_assets() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/env/releases/tags/beta-v1.2.3"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Not Found" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/env/releases"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({ "tag_name": "beta-v1.2.3", "prerelease": true, "generate_release_notes": true })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(release_json(&server, json!([]))))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/uploads/releases/7/assets"))
+            .and(query_param("name", "checksum.txt"))
+            .and(header("content-type", "text/plain"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(asset_json(11, "checksum.txt", 6)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ReleaseClient::with_api_url("secret", "acme/env", &server.uri()).unwrap();
+        let (release, created) = client.create_or_get(&new_release()).await.unwrap();
+        assert!(created);
+        assert_eq!(release.html_url, "https://github.com/acme/env/releases/tag/beta-v1.2.3");
+
+        let dir = tempfile::tempdir().unwrap();
+        let checksum = dir.path().join("checksum.txt");
+        std::fs::write(&checksum, "abc  x").unwrap();
+        let asset = client.upload_asset(&release, &checksum).await.unwrap();
+        assert_eq!(asset.name, "checksum.txt");
+        assert!(asset.browser_download_url.ends_with("/beta-v1.2.3/checksum.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_reuses_release_and_replaces_assets() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/env/releases/tags/beta-v1.2.3"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(release_json(&server, json!([asset_json(5, "dev-environment.tar.gz", 1)]))))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/env/releases"))
+            .respond_with(ResponseTemplate::new(422))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/repos/acme/env/releases/assets/5"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/uploads/releases/7/assets"))
+            .and(query_param("name", "dev-environment.tar.gz"))
+            .and(header("content-type", "application/gzip"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(asset_json(12, "dev-environment.tar.gz", 3)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = ReleaseClient::with_api_url("secret", "acme/env", &server.uri()).unwrap();
+        let (release, created) = client.create_or_get(&new_release()).await.unwrap();
+        assert!(!created);
+
+        let dir = tempfile::tempdir().unwrap();
+        let tarball = dir.path().join("dev-environment.tar.gz");
+        std::fs::write(&tarball, [0x1f, 0x8b, 0x08]).unwrap();
+        assert_eq!(client.upload_asset(&release, &tarball).await.unwrap().id, 12);
+    }
+}
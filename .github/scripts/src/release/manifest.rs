@@ -0,0 +1,143 @@
+//! Machine-readable description of a published release
+//!
+//! The manifest is uploaded next to the other assets as
+//! `release-manifest.json`, so installers can find every download of a
+//! release, verify it against its SHA-256 and pull the Docker image by digest
+//! without scraping the release page:
+//!
+//! ```json
+//! {
+//!   "schema": 1,
+//!   "version": "beta-v1.2.3",
+//!   "channel": "beta",
+//!   "commit": "…",
+//!   "release_url": "https://github.com/owner/repo/releases/tag/beta-v1.2.3",
+//!   "image": { "reference": "owner/image:beta", "digest": "sha256:…" },
+//!   "assets": [{ "name": "dev-environment.tar.gz", "url": "…", "sha256": "…", "size": 1024 }]
+//! }
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::version::{Channel, Version};
+
+/// File name of the manifest asset
+pub const MANIFEST_FILE: &str = "release-manifest.json";
+
+/// Manifest format version, bumped on incompatible changes
+pub const MANIFEST_SCHEMA: u32 = 1;
+
+/// The Docker image published with a release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageInfo {
+    /// Tag the image was pushed under, e.g. `owner/image:beta`
+    pub reference: String,
+    /// Content digest, e.g. `sha256:…`; pull with `reference@digest` to pin it
+    pub digest: String,
+}
+
+/// A downloadable file of the release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestAsset {
+    pub name: String,
+    pub url: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub schema: u32,
+    pub version: String,
+    /// `beta` or `stable`; absent for versions outside the release scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    pub commit: String,
+    pub release_url: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInfo>,
+    pub assets: Vec<ManifestAsset>,
+}
+
+impl ReleaseManifest {
+    pub fn new(version: &str, commit: &str, release_url: &str) -> Self {
+        let channel = version.parse::<Version>().ok().map(|version| match version.release_channel() {
+            Channel::Beta => "beta".to_string(),
+            Channel::Stable => "stable".to_string(),
+        });
+
+        Self {
+            schema: MANIFEST_SCHEMA,
+            version: version.to_string(),
+            channel,
+            commit: commit.to_string(),
+            release_url: release_url.to_string(),
+            created_at: Utc::now(),
+            image: None,
+            assets: Vec::new(),
+        }
+    }
+
+    pub fn with_image(mut self, image: Option<ImageInfo>) -> Self {
+        self.image = image;
+        self
+    }
+
+    /// Describe the local file at `path`, downloadable from `url`
+    pub fn add_asset(&mut self, path: &Path, url: &str) -> Result<()> {
+        let (sha256, size) = sha256_file(path)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        self.assets.push(ManifestAsset { name, url: url.to_string(), sha256, size });
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+}
+
+/// Hex SHA-256 and size of a file
+pub fn sha256_file(path: &Path) -> Result<(String, u64)> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_describes_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let checksum = dir.path().join("checksum.txt");
+        std::fs::write(&checksum, "abc").unwrap();
+
+        let image = ImageInfo { reference: "acme/env:beta".to_string(), digest: "sha256:1234".to_string() };
+        let mut manifest = ReleaseManifest::new("beta-v1.2.3", &"a".repeat(40), "https://example.com/r").with_image(Some(image));
+        manifest.add_asset(&checksum, "https://example.com/checksum.txt").unwrap();
+
+        assert_eq!(manifest.channel.as_deref(), Some("beta"));
+        assert_eq!(manifest.assets[0].size, 3);
+        assert_eq!(manifest.assets[0].sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let json: serde_json::Value = serde_json::from_str(&manifest.to_json().unwrap()).unwrap();
+        assert_eq!(json["schema"], 1);
+        assert_eq!(json["image"]["digest"], "sha256:1234");
+        assert_eq!(json["assets"][0]["name"], "checksum.txt");
+        assert_eq!(ReleaseManifest::new("v2.0.0", "b", "u").channel.as_deref(), Some("stable"));
+        assert_eq!(ReleaseManifest::new("nightly", "b", "u").channel, None);
+    }
+}
@@ -0,0 +1,392 @@
+//! Release notes grouped by change type
+//!
+//! The notes cover the first-parent history between the previous release tag
+//! of the same channel and the released commit, so a merged pull request shows
+//! up once, as its merge or squash commit. Each change is filed under a
+//! [`Category`]: a pull request label that maps to one wins, otherwise the
+//! Conventional Commit type of the PR title or commit header decides. The
+//! notes end with the contributors and the artifacts built for the release.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::process::Command;
+
+use crate::version::{conventional::ConventionalCommit, Version};
+
+/// Section a change is listed under, in the order the sections appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Features,
+    Fixes,
+    Docs,
+    Ci,
+    Dependencies,
+    Other,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Features,
+        Category::Fixes,
+        Category::Docs,
+        Category::Ci,
+        Category::Dependencies,
+        Category::Other,
+    ];
+
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Category::Features => "✨ Features",
+            Category::Fixes => "🐛 Fixes",
+            Category::Docs => "📚 Documentation",
+            Category::Ci => "🔧 CI",
+            Category::Dependencies => "📦 Dependencies",
+            Category::Other => "Other Changes",
+        }
+    }
+
+    /// Category of a Conventional Commit, e.g. `feat` or `chore(deps)`
+    pub fn from_commit(commit: &ConventionalCommit) -> Self {
+        if commit.kind == "deps" || matches!(commit.scope.as_deref(), Some("deps" | "deps-dev")) {
+            return Category::Dependencies;
+        }
+        match commit.kind.as_str() {
+            "feat" => Category::Features,
+            "fix" | "perf" => Category::Fixes,
+            "docs" => Category::Docs,
+            "ci" | "build" => Category::Ci,
+            _ => Category::Other,
+        }
+    }
+
+    /// Category a pull request label stands for, if any
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().as_str() {
+            "feature" | "enhancement" | "feat" => Some(Category::Features),
+            "bug" | "fix" | "bugfix" => Some(Category::Fixes),
+            "documentation" | "docs" => Some(Category::Docs),
+            "ci" | "github_actions" | "github-actions" => Some(Category::Ci),
+            "dependencies" | "deps" => Some(Category::Dependencies),
+            _ => None,
+        }
+    }
+}
+
+/// A commit on the release branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub author: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PullRequestUser {
+    pub login: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PullRequestLabel {
+    pub name: String,
+}
+
+/// A pull request as returned by the API, reduced to what the notes use
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub user: Option<PullRequestUser>,
+    #[serde(default)]
+    pub labels: Vec<PullRequestLabel>,
+}
+
+/// One line of the notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub category: Category,
+    pub summary: String,
+    pub sha: String,
+    pub pr: Option<u64>,
+    /// GitHub login, or the commit author's name when there is no pull request
+    pub author: String,
+}
+
+impl Change {
+    /// Describe a commit, using its pull request when one was found
+    ///
+    /// Returns `None` for merges that do not come from a pull request.
+    pub fn new(commit: &CommitInfo, pr: Option<&PullRequest>) -> Option<Self> {
+        let header = commit.message.lines().next().unwrap_or_default().trim();
+        let number = pr.map(|pr| pr.number).or_else(|| pr_number(&commit.message));
+        if number.is_none() && header.starts_with("Merge ") {
+            return None;
+        }
+
+        // A merge commit's own header says nothing; its body is the PR title
+        let title = match pr {
+            Some(pr) => pr.title.trim().to_string(),
+            None if header.starts_with("Merge pull request") => commit.message
+                .lines()
+                .skip(1)
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or(header)
+                .to_string(),
+            None => header.to_string(),
+        };
+
+        let conventional = ConventionalCommit::parse(&title);
+        let label_category = pr
+            .into_iter()
+            .flat_map(|pr| &pr.labels)
+            .find_map(|label| Category::from_label(&label.name));
+        let category = label_category
+            .or_else(|| conventional.as_ref().map(Category::from_commit))
+            .unwrap_or(Category::Other);
+
+        let summary = conventional.map(|commit| commit.description).unwrap_or(title);
+        let summary = strip_pr_suffix(&summary).to_string();
+        let author = pr
+            .and_then(|pr| pr.user.as_ref())
+            .map(|user| format!("@{}", user.login))
+            .unwrap_or_else(|| commit.author.clone());
+
+        Some(Self { category, summary, sha: commit.sha.clone(), pr: number, author })
+    }
+}
+
+/// Pull request number of a merge (`Merge pull request #12 from ...`) or squash (`... (#12)`) commit
+pub fn pr_number(message: &str) -> Option<u64> {
+    let header = message.lines().next()?.trim();
+    let digits = match header.strip_prefix("Merge pull request #") {
+        Some(rest) => rest,
+        None => header.strip_suffix(')')?.rsplit_once("(#")?.1,
+    };
+    let end = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+fn strip_pr_suffix(summary: &str) -> &str {
+    match summary.trim_end().strip_suffix(')').and_then(|rest| rest.rsplit_once(" (#")) {
+        Some((summary, number)) if number.bytes().all(|b| b.is_ascii_digit()) => summary,
+        _ => summary,
+    }
+}
+
+/// Highest tag below `current` that releases to the same channel
+pub fn previous_tag<'a>(tags: impl IntoIterator<Item = &'a str>, current: &Version) -> Option<&'a str> {
+    tags.into_iter()
+        .filter_map(|tag| tag.trim().parse::<Version>().ok().map(|version| (version, tag.trim())))
+        .filter(|(version, _)| version.release_channel() == current.release_channel())
+        .filter(|(version, _)| version.cmp_precedence(current).is_lt())
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+/// First-parent commits after `from` up to `to`, oldest first; the whole history without `from`
+pub fn commits_between(from: Option<&str>, to: &str) -> Result<Vec<CommitInfo>> {
+    let range = match from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
+    };
+    let output = Command::new("git")
+        .args(["log", "--first-parent", "--reverse", "--format=%H%x1f%an%x1f%B%x1e", &range])
+        .output()
+        .context("Failed to run git log")?;
+    if !output.status.success() {
+        bail!("git log {} failed: {}", range, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim().splitn(3, '\x1f');
+            Some(CommitInfo {
+                sha: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                message: fields.next()?.trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// A stable release promoted from a beta without rebuilding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Promotion {
+    /// Tag of the promoted beta
+    pub from: String,
+    pub commit: String,
+    pub image_digest: Option<String>,
+}
+
+/// Release notes for one version
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub previous: Option<String>,
+    /// `owner/repo`, for the compare link
+    pub repository: Option<String>,
+    pub changes: Vec<Change>,
+    pub image_tags: Vec<String>,
+    /// Contents of the release's checksum file
+    pub checksum: Option<String>,
+    pub promotion: Option<Promotion>,
+}
+
+impl ReleaseNotes {
+    /// Notes for `commits`, with pull requests looked up by number
+    pub fn new(version: &str, previous: Option<&str>, commits: &[CommitInfo], pulls: &HashMap<u64, PullRequest>) -> Self {
+        let changes = commits
+            .iter()
+            .filter_map(|commit| Change::new(commit, pr_number(&commit.message).and_then(|number| pulls.get(&number))))
+            .collect();
+
+        Self {
+            version: version.to_string(),
+            previous: previous.map(str::to_string),
+            changes,
+            ..Self::default()
+        }
+    }
+
+    /// Everyone with a change in the release, sorted
+    pub fn contributors(&self) -> BTreeSet<&str> {
+        self.changes.iter().map(|change| change.author.as_str()).collect()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        if let Some(promotion) = &self.promotion {
+            let _ = writeln!(out, "## Promotion");
+            let _ = writeln!(out);
+            let _ = write!(out, "Promoted from `{}` without rebuilding: commit `{}`", promotion.from, promotion.commit);
+            match &promotion.image_digest {
+                Some(digest) => {
+                    let _ = writeln!(out, " and image `{}`.", digest);
+                },
+                None => {
+                    let _ = writeln!(out, ".");
+                },
+            }
+            let _ = writeln!(out);
+        }
+
+        let _ = writeln!(out, "## What's Changed");
+
+        if self.changes.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "No changes since {}.", self.previous.as_deref().unwrap_or("the start of the history"));
+        }
+        for category in Category::ALL {
+            let changes: Vec<&Change> = self.changes.iter().filter(|change| change.category == category).collect();
+            if changes.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out);
+            let _ = writeln!(out, "### {}", category.heading());
+            let _ = writeln!(out);
+            for change in changes {
+                let reference = match change.pr {
+                    Some(number) => format!("#{}", number),
+                    None => change.sha[..change.sha.len().min(7)].to_string(),
+                };
+                let _ = writeln!(out, "- {} ({}) by {}", change.summary, reference, change.author);
+            }
+        }
+
+        let contributors = self.contributors();
+        if !contributors.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "## Contributors");
+            let _ = writeln!(out);
+            let _ = writeln!(out, "{}", contributors.into_iter().collect::<Vec<_>>().join(", "));
+        }
+
+        if !self.image_tags.is_empty() || self.checksum.is_some() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "## Artifacts");
+            let _ = writeln!(out);
+            for tag in &self.image_tags {
+                let _ = writeln!(out, "- Docker image: `{}`", tag);
+            }
+            if let Some(checksum) = &self.checksum {
+                let _ = writeln!(out);
+                let _ = writeln!(out, "```text");
+                let _ = writeln!(out, "{}", checksum.trim_end());
+                let _ = writeln!(out, "```");
+            }
+        }
+
+        if let (Some(repository), Some(previous)) = (&self.repository, &self.previous) {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "**Full Changelog**: https://github.com/{}/compare/{}...{}", repository, previous, self.version);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(sha: char, author: &str, message: &str) -> CommitInfo {
+        CommitInfo { sha: sha.to_string().repeat(40), author: author.to_string(), message: message.to_string() }
+    }
+
+    #[test]
+    fn test_groups_changes_by_label_and_type() {
+        let commits = [
+            commit('a', "Alice", "feat(queue): add prune mode (#12)"),
+            commit('b', "Bob", "Merge pull request #13 from bob/docs\n\nUpdate the release schedule"),
+            commit('c', "Carol", "fix: handle empty queue"),
+            commit('d', "dependabot[bot]", "chore(deps): bump serde from 1.0.1 to 1.0.2 (#14)"),
+            commit('e', "Alice", "Merge branch 'beta'"),
+            commit('f', "Dan", "Tidy workflow files"),
+        ];
+        let mut pulls = HashMap::new();
+        pulls.insert(13, PullRequest {
+            number: 13,
+            title: "Update the release schedule".to_string(),
+            user: Some(PullRequestUser { login: "bob".to_string() }),
+            labels: vec![PullRequestLabel { name: "documentation".to_string() }],
+        });
+
+        let mut notes = ReleaseNotes::new("beta-v1.3.0", Some("beta-v1.2.0"), &commits, &pulls);
+        notes.repository = Some("acme/env".to_string());
+        notes.image_tags = vec!["acme/env:beta-v1.3.0".to_string()];
+        notes.checksum = Some("abc123  dev-environment.tar.gz\n".to_string());
+
+        let categories: Vec<Category> = notes.changes.iter().map(|change| change.category).collect();
+        assert_eq!(categories, [Category::Features, Category::Docs, Category::Fixes, Category::Dependencies, Category::Other]);
+        assert_eq!(notes.changes[0].summary, "add prune mode");
+        assert_eq!(notes.changes[0].pr, Some(12));
+        assert_eq!(notes.contributors().into_iter().collect::<Vec<_>>(), ["@bob", "Alice", "Carol", "Dan", "dependabot[bot]"]);
+
+        let markdown = notes.to_markdown();
+        assert!(markdown.contains("### ✨ Features\n\n- add prune mode (#12) by Alice\n"));
+        assert!(markdown.contains("### 📚 Documentation\n\n- Update the release schedule (#13) by @bob\n"));
+        assert!(markdown.contains("- handle empty queue (ccccccc) by Carol"));
+        assert!(markdown.find("🐛 Fixes").unwrap() < markdown.find("📦 Dependencies").unwrap());
+        assert!(markdown.contains("- Docker image: `acme/env:beta-v1.3.0`"));
+        assert!(markdown.contains("abc123  dev-environment.tar.gz\n```"));
+        assert!(markdown.contains("compare/beta-v1.2.0...beta-v1.3.0"));
+
+        notes.promotion = Some(Promotion { from: "beta-v1.3.0".to_string(), commit: "f".repeat(40), image_digest: None });
+        assert!(notes.to_markdown().starts_with(&format!("## Promotion\n\nPromoted from `beta-v1.3.0` without rebuilding: commit `{}`.\n", "f".repeat(40))));
+    }
+
+    #[test]
+    fn test_previous_tag_and_pr_numbers() {
+        let tags = ["beta-v1.2.0", "beta-v1.2.3", "stable-v1.2.9", "v1.2.2-beta", "nightly", "beta-v1.4.0"];
+        let current: Version = "beta-v1.3.0".parse().unwrap();
+        assert_eq!(previous_tag(tags, &current), Some("beta-v1.2.3"));
+        assert_eq!(previous_tag(tags, &"stable-v1.0.0".parse().unwrap()), None);
+
+        assert_eq!(pr_number("Merge pull request #42 from a/b"), Some(42));
+        assert_eq!(pr_number("fix: typo (#7)\n\nbody"), Some(7));
+        assert_eq!(pr_number("fix: typo (see #7)"), None);
+        assert_eq!(pr_number("fix: typo"), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Promotion of a beta release to stable
+//!
+//! A stable release ships exactly what was tested as a beta: the same commit
+//! and the same Docker image, re-tagged by digest instead of rebuilt. Before
+//! promoting, the beta's release and its [manifest](super::manifest) are
+//! checked: the release must exist, its manifest must describe its commit,
+//! pin an image digest and carry a checksum for every asset that is still
+//! attached with the recorded size.
+
+use anyhow::{bail, Result};
+use std::fmt::Write as _;
+use std::process::Command;
+
+use super::manifest::{ImageInfo, ReleaseManifest, MANIFEST_FILE};
+use super::Release;
+use crate::version::{Channel, Version};
+
+/// Outcome of one promotion check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    pub fn new(name: &str, passed: bool, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), passed, detail: detail.into() }
+    }
+}
+
+/// All checks of a promotion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verification {
+    pub checks: Vec<Check>,
+}
+
+impl Verification {
+    pub fn push(&mut self, check: Check) {
+        self.checks.push(check);
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Markdown table for `GITHUB_STEP_SUMMARY`
+    pub fn to_markdown(&self, beta: &str, stable: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "## 🚀 Promotion of `{}` to `{}`", beta, stable);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "| Check | Result | Detail |");
+        let _ = writeln!(out, "|-------|--------|--------|");
+        for check in &self.checks {
+            let result = if check.passed { "✅" } else { "❌" };
+            let _ = writeln!(out, "| {} | {} | {} |", check.name, result, check.detail.replace('|', "\\|"));
+        }
+        out
+    }
+}
+
+/// Stable version a beta is promoted to, e.g. `beta-v1.2.3-4` to `stable-v1.2.3`
+pub fn stable_version(beta: &Version) -> Result<Version> {
+    if beta.release_channel() != Channel::Beta {
+        bail!("{} is not a beta release", beta.to_tag());
+    }
+    Ok(Version::new(Some(Channel::Stable), beta.major, beta.minor, beta.patch))
+}
+
+/// Check a beta release and its manifest, expecting both to describe `commit`
+pub fn verify_release(release: &Release, manifest: &ReleaseManifest, commit: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    checks.push(Check::new(
+        "Manifest version",
+        manifest.version == release.tag_name,
+        format!("manifest describes `{}`", manifest.version),
+    ));
+    checks.push(Check::new(
+        "Manifest commit",
+        manifest.commit == commit,
+        format!("`{}`", manifest.commit),
+    ));
+
+    let image = match &manifest.image {
+        Some(image) if is_digest(&image.digest) => Check::new("Image digest", true, format!("`{}@{}`", image.reference, image.digest)),
+        Some(image) => Check::new("Image digest", false, format!("`{}` is not a sha256 digest", image.digest)),
+        None => Check::new("Image digest", false, "the manifest pins no image"),
+    };
+    checks.push(image);
+
+    let mut problems = Vec::new();
+    if manifest.assets.is_empty() {
+        problems.push("the manifest lists no assets".to_string());
+    }
+    for asset in &manifest.assets {
+        if !is_sha256(&asset.sha256) {
+            problems.push(format!("{} has no sha256", asset.name));
+        }
+        match release.asset(&asset.name) {
+            Some(attached) if attached.size == asset.size => {},
+            Some(attached) => problems.push(format!("{} is {} bytes, manifest says {}", asset.name, attached.size, asset.size)),
+            None => problems.push(format!("{} is not attached", asset.name)),
+        }
+    }
+    let detail = if problems.is_empty() {
+        format!("{} assets with checksums", manifest.assets.len())
+    } else {
+        problems.join("; ")
+    };
+    checks.push(Check::new("Asset checksums", problems.is_empty(), detail));
+
+    checks
+}
+
+/// Check a beta release that has no manifest to verify
+pub fn missing_manifest(release: &Release) -> Check {
+    Check::new("Release manifest", false, format!("{} has no {}", release.tag_name, MANIFEST_FILE))
+}
+
+/// Repository part of an image reference, e.g. `owner/image` for `owner/image:beta`
+pub fn image_repository(reference: &str) -> &str {
+    let reference = reference.split('@').next().unwrap_or(reference);
+    match reference.rsplit_once(':') {
+        // A colon before the last slash is a registry port, not a tag
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => reference,
+    }
+}
+
+/// Point `tags` of the image's repository at its digest without pulling or rebuilding
+pub fn retag_command(image: &ImageInfo, tags: &[String]) -> Command {
+    let repository = image_repository(&image.reference);
+    let mut command = Command::new("docker");
+    command.args(["buildx", "imagetools", "create"]);
+    for tag in tags {
+        command.args(["--tag", &format!("{}:{}", repository, tag)]);
+    }
+    command.arg(format!("{}@{}", repository, image.digest));
+    command
+}
+
+fn is_digest(digest: &str) -> bool {
+    digest.strip_prefix("sha256:").is_some_and(is_sha256)
+}
+
+fn is_sha256(hex: &str) -> bool {
+    hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release::manifest::ManifestAsset;
+    use crate::release::ReleaseAsset;
+
+    fn release(assets: &[(&str, u64)]) -> Release {
+        Release {
+            id: 7,
+            tag_name: "beta-v1.2.3-4".to_string(),
+            html_url: "https://github.com/acme/env/releases/tag/beta-v1.2.3-4".to_string(),
+            upload_url: String::new(),
+            draft: false,
+            prerelease: true,
+            assets: assets
+                .iter()
+                .enumerate()
+                .map(|(id, (name, size))| ReleaseAsset {
+                    id: id as u64,
+                    name: name.to_string(),
+                    size: *size,
+                    browser_download_url: format!("https://example.com/{}", name),
+                })
+                .collect(),
+        }
+    }
+
+    fn manifest() -> ReleaseManifest {
+        let mut manifest = ReleaseManifest::new("beta-v1.2.3-4", &"a".repeat(40), "u").with_image(Some(ImageInfo {
+            reference: "acme/env:beta".to_string(),
+            digest: format!("sha256:{}", "b".repeat(64)),
+        }));
+        manifest.assets.push(ManifestAsset { name: "dev-environment.tar.gz".to_string(), url: "u".to_string(), sha256: "c".repeat(64), size: 10 });
+        manifest
+    }
+
+    #[test]
+    fn test_verifies_release_against_manifest() {
+        let commit = "a".repeat(40);
+        let checks = verify_release(&release(&[("dev-environment.tar.gz", 10), (MANIFEST_FILE, 2)]), &manifest(), &commit);
+        assert!(checks.iter().all(|check| check.passed), "{:?}", checks);
+
+        let checks = verify_release(&release(&[("dev-environment.tar.gz", 11)]), &manifest(), &"d".repeat(40));
+        let failed: Vec<&str> = checks.iter().filter(|check| !check.passed).map(|check| check.name.as_str()).collect();
+        assert_eq!(failed, ["Manifest commit", "Asset checksums"]);
+        assert!(checks[3].detail.contains("is 11 bytes, manifest says 10"));
+
+        let mut unpinned = manifest();
+        unpinned.image = None;
+        assert!(!verify_release(&release(&[("dev-environment.tar.gz", 10)]), &unpinned, &commit)[2].passed);
+
+        let mut verification = Verification::default();
+        checks.into_iter().for_each(|check| verification.push(check));
+        assert!(!verification.passed());
+        assert!(verification.to_markdown("beta-v1.2.3-4", "stable-v1.2.3").contains("| Manifest commit | ❌ |"));
+    }
+
+    #[test]
+    fn test_stable_version_and_retag() {
+        assert_eq!(stable_version(&"beta-v1.2.3-4".parse().unwrap()).unwrap().to_string(), "stable-v1.2.3");
+        assert_eq!(stable_version(&"v1.2.3-beta.4".parse().unwrap()).unwrap().to_string(), "stable-v1.2.3");
+        assert!(stable_version(&"stable-v1.2.3".parse().unwrap()).is_err());
+
+        assert_eq!(image_repository("acme/env:beta"), "acme/env");
+        assert_eq!(image_repository("registry:5000/acme/env"), "registry:5000/acme/env");
+        assert_eq!(image_repository("registry:5000/acme/env:beta@sha256:00"), "registry:5000/acme/env");
+
+        let image = ImageInfo { reference: "acme/env:beta".to_string(), digest: "sha256:ff".to_string() };
+        let command = retag_command(&image, &["stable".to_string(), "stable-v1.2.3".to_string()]);
+        let args: Vec<String> = command.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect();
+        assert_eq!(args, ["buildx", "imagetools", "create", "--tag", "acme/env:stable", "--tag", "acme/env:stable-v1.2.3", "acme/env@sha256:ff"]);
+    }
+}
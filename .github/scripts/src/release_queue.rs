@@ -0,0 +1,1374 @@
+//! Release queue storage shared by the queue workflow scripts
+//!
+//! This module owns the on-disk schema of `.github/release_queue/{branch}.json`
+//! and the operations the queue binaries perform on it. Older layouts (the
+//! unversioned `{"items": [...]}` files and the single `queue.json` array) are
+//! upgraded transparently when the queue is opened.
+//!
+//! Items are ordered by priority lane first and their sequence number second,
+//! which follows queue time unless an entry was moved by hand, so a
+//! hotfix can overtake routine commits; every priority decision is recorded
+//! in the queue's audit trail. Each item also tracks where its release is in
+//! the [`lifecycle`], and failed releases stay queued until they are retried
+//! or cancelled. Processed entries leave timing samples behind
+//! that feed the ETA estimates in [`eta`], and [`policy`] decides when a
+//! queue is ready to be released.
+//!
+//! Entries that leave a queue are appended to the [`history`] log, which also
+//! feeds the release summaries. [`report`] renders the current queue for job
+//! summaries, PR comments and terminals.
+//!
+//! Commits are checked against the local clone with [`commits`] before they
+//! are queued, and `prune` uses the same checks to drop entries that can no
+//! longer be released. Rolling back a release puts the commits it shipped
+//! back in the queue, restored from the history log.
+//!
+//! Every read-modify-write happens under an advisory lock on the queue
+//! directory, and files are replaced atomically so an interrupted runner never
+//! leaves a half-written queue behind.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod commits;
+pub mod eta;
+pub mod history;
+pub mod lifecycle;
+pub mod policy;
+pub mod report;
+
+use commits::{CommitVerifier, Reachability};
+use eta::{Estimate, ReleaseSample, ETA_WINDOW};
+use history::{HistoryEntry, HistoryEvent};
+use lifecycle::{EntryState, InvalidTransition, Transition};
+use policy::{Readiness, ReleasePolicy};
+use report::StatusReport;
+
+/// Default location of the queue files, relative to the repository root
+pub const QUEUE_DIR: &str = ".github/release_queue";
+
+/// Current version of the queue file schema
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Branches that are allowed to have a release queue
+pub const RELEASE_BRANCHES: [&str; 2] = ["beta", "main"];
+
+/// How long to wait for another job to release the queue lock
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Name of the pre-versioning single-file queue
+const LEGACY_QUEUE_FILE: &str = "queue.json";
+
+/// Advisory lock file guarding the queue directory
+const LOCK_FILE: &str = ".queue.lock";
+
+/// Delay between attempts to take the queue lock
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Release lane of a queued commit; earlier variants are released first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Urgent fixes that bypass routine work
+    Hotfix,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Hotfix => "hotfix",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "hotfix" => Ok(Priority::Hotfix),
+            "normal" | "" => Ok(Priority::Normal),
+            "low" => Ok(Priority::Low),
+            other => bail!("Unknown priority '{}': expected hotfix, normal or low", other),
+        }
+    }
+}
+
+/// A single commit waiting to be released
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueItem {
+    /// Full SHA of the queued commit
+    pub commit: String,
+    /// When the commit entered the queue
+    pub date: DateTime<Utc>,
+    /// Pull request that brought the commit in, if known
+    #[serde(default)]
+    pub pr: Option<u64>,
+    /// Release lane of the commit
+    #[serde(default)]
+    pub priority: Priority,
+    /// Release order within the lane, lowest first; renumbered on every change
+    #[serde(default)]
+    pub sequence: u64,
+    /// When the release pipeline picked the commit up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// Where the release of this commit currently is
+    #[serde(default)]
+    pub state: EntryState,
+    /// State changes, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<Transition>,
+    /// How many times a failed release was sent back to pending
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+}
+
+impl QueueItem {
+    /// A freshly queued, pending commit
+    pub fn new(commit: &str, pr: Option<u64>, priority: Priority) -> Self {
+        Self {
+            commit: commit.to_string(),
+            date: Utc::now(),
+            pr,
+            priority,
+            sequence: 0,
+            started_at: None,
+            state: EntryState::Pending,
+            transitions: Vec::new(),
+            retries: 0,
+        }
+    }
+
+    /// Move the entry to another lifecycle state
+    ///
+    /// Fails with [`InvalidTransition`] for changes outside the lifecycle, and
+    /// when a failure is recorded without a reason.
+    pub fn transition(&mut self, to: EntryState, reason: Option<&str>) -> Result<()> {
+        if !self.state.can_transition_to(to) {
+            return Err(InvalidTransition { commit: self.commit.clone(), from: self.state, to }.into());
+        }
+        let reason = reason.map(str::trim).filter(|reason| !reason.is_empty());
+        if to == EntryState::Failed && reason.is_none() {
+            bail!("A reason is required to mark commit {} as failed", self.commit);
+        }
+
+        match (self.state, to) {
+            (EntryState::Failed, EntryState::Pending) => {
+                self.retries += 1;
+                self.started_at = None;
+            },
+            (_, EntryState::Building) => self.started_at = Some(Utc::now()),
+            _ => {},
+        }
+
+        self.record(to, reason);
+        Ok(())
+    }
+
+    /// Latest failure reason, if the entry is currently failed
+    pub fn failure_reason(&self) -> Option<&str> {
+        if self.state != EntryState::Failed {
+            return None;
+        }
+        self.transitions.last().and_then(|transition| transition.reason.as_deref())
+    }
+
+    fn record(&mut self, to: EntryState, reason: Option<&str>) {
+        self.transitions.push(Transition {
+            from: self.state,
+            to,
+            at: Utc::now(),
+            reason: reason.map(str::to_string),
+        });
+        self.state = to;
+    }
+}
+
+/// Record of a priority decision on a queued commit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    /// Who made the change, usually `GITHUB_ACTOR`
+    pub actor: String,
+    pub commit: String,
+    /// Previous priority; `None` when the commit entered the queue with `to`
+    pub from: Option<Priority>,
+    pub to: Priority,
+    pub reason: String,
+}
+
+/// Who is asking for a priority and why
+#[derive(Debug, Clone, Copy)]
+pub struct PriorityRequest<'a> {
+    pub priority: Priority,
+    pub actor: &'a str,
+    pub reason: &'a str,
+}
+
+/// Contents of a per-branch queue file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueFile {
+    /// Schema version; files written before versioning deserialize as 0
+    #[serde(default)]
+    pub version: u32,
+    /// Branch this queue releases to
+    #[serde(default)]
+    pub branch: String,
+    /// Queued commits in release order
+    #[serde(default)]
+    pub items: Vec<QueueItem>,
+    /// Priority decisions, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audit: Vec<AuditEntry>,
+    /// Timings of the most recently processed entries, used for ETAs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub throughput: Vec<ReleaseSample>,
+    /// When entries were last drained after a release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_released_at: Option<DateTime<Utc>>,
+}
+
+impl QueueFile {
+    /// Create an empty queue for a branch
+    pub fn new(branch: &str) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            branch: branch.to_string(),
+            items: Vec::new(),
+            audit: Vec::new(),
+            throughput: Vec::new(),
+            last_released_at: None,
+        }
+    }
+
+    /// Estimated wait for the entry at a 1-based position, `batch_size` commits per release
+    pub fn estimate(&self, position: usize, batch_size: usize) -> Estimate {
+        Estimate::for_position(&self.throughput, position, batch_size)
+    }
+
+    /// Restore release order: priority lane first, then sequence
+    ///
+    /// Files written before sequences existed have none, so their entries
+    /// fall back to queue time.
+    pub fn sort(&mut self) {
+        self.items.sort_by_key(|item| (item.priority, item.sequence, item.date));
+        self.resequence();
+    }
+
+    /// Queue an entry behind the entries of its lane that were queued before it
+    pub fn insert(&mut self, item: QueueItem) {
+        let index = self.items
+            .iter()
+            .position(|queued| (queued.priority, queued.date) > (item.priority, item.date))
+            .unwrap_or(self.items.len());
+        self.items.insert(index, item);
+        self.resequence();
+    }
+
+    /// Number the entries in their current order
+    fn resequence(&mut self) {
+        for (index, item) in self.items.iter_mut().enumerate() {
+            item.sequence = index as u64 + 1;
+        }
+    }
+
+    /// Move a queued commit to another lane, recording the change
+    ///
+    /// Returns false when the commit already had the requested priority.
+    fn reprioritize(&mut self, sha: &str, request: PriorityRequest<'_>) -> bool {
+        let Some(index) = self.items.iter().position(|item| item.commit == sha) else {
+            return false;
+        };
+        if self.items[index].priority == request.priority {
+            return false;
+        }
+        let mut item = self.items.remove(index);
+
+        self.audit.push(AuditEntry {
+            at: Utc::now(),
+            actor: request.actor.to_string(),
+            commit: sha.to_string(),
+            from: Some(item.priority),
+            to: request.priority,
+            reason: request.reason.to_string(),
+        });
+        // The new lane takes the commit in by its queue time
+        item.priority = request.priority;
+        self.insert(item);
+        true
+    }
+
+    /// 1-based position of a commit in the queue
+    pub fn position(&self, sha: &str) -> Option<usize> {
+        self.items.iter().position(|item| item.commit == sha).map(|i| i + 1)
+    }
+
+    /// Check whether a commit is already queued
+    pub fn contains(&self, sha: &str) -> bool {
+        self.position(sha).is_some()
+    }
+
+    /// Move a commit to another 1-based position within its priority lane
+    ///
+    /// Only the sequence changes; the queue time, and with it the age the
+    /// batching policy sees, stays as it was. Moving across lanes is a
+    /// priority change and has to go through [`ReleaseQueue::set_priority`].
+    pub fn move_to(&mut self, sha: &str, position: usize) -> Result<usize> {
+        let Some(current) = self.position(sha) else {
+            bail!("Commit {} is not queued", sha);
+        };
+        let lane = self.items[current - 1].priority;
+        let first = self.items.iter().position(|item| item.priority == lane).unwrap_or(0) + 1;
+        let last = self.items.iter().rposition(|item| item.priority == lane).unwrap_or(0) + 1;
+
+        if position < first || position > last {
+            bail!(
+                "Commit {} is in the {} lane, which spans positions {}-{}; change its priority to move it further",
+                sha, lane, first, last
+            );
+        }
+
+        let item = self.items.remove(current - 1);
+        self.items.insert(position - 1, item);
+        self.resequence();
+
+        Ok(position)
+    }
+
+    /// Mark a commit and the entries queued before it as released and drop them
+    fn release_through(&mut self, sha: &str) -> Vec<QueueItem> {
+        let Some(position) = self.position(sha) else {
+            return Vec::new();
+        };
+
+        let mut released = Vec::new();
+        let mut kept = Vec::new();
+        for (index, mut item) in self.items.drain(..).enumerate() {
+            let failed_elsewhere = item.state == EntryState::Failed && item.commit != sha;
+            if index >= position || failed_elsewhere {
+                kept.push(item);
+                continue;
+            }
+            item.record(EntryState::Released, None);
+            released.push(item);
+        }
+        self.items = kept;
+        self.last_released_at = Some(Utc::now());
+
+        if let Some(started_at) = released.last().and_then(|item| item.started_at) {
+            self.throughput.push(ReleaseSample {
+                commit: sha.to_string(),
+                started_at,
+                finished_at: Utc::now(),
+            });
+            let excess = self.throughput.len().saturating_sub(ETA_WINDOW);
+            self.throughput.drain(..excess);
+        }
+
+        released
+    }
+
+    /// Return the commits of a rolled back release to pending
+    ///
+    /// `released` are the history entries the release drained; they are queued
+    /// again with their original lane and queue time. `commit`, the release
+    /// commit, is reset in place if the release failed before draining it.
+    /// Commits that are already pending are left alone. Rolling back is outside
+    /// the normal lifecycle, so the reset is recorded with `reason`.
+    pub fn restore_release(&mut self, released: &[HistoryEntry], commit: Option<&str>, reason: &str) -> Vec<QueueItem> {
+        let mut restored = Vec::new();
+
+        let candidates = released.iter().map(|entry| (entry.commit.as_str(), Some(entry))).chain(commit.map(|sha| (sha, None)));
+        for (sha, entry) in candidates {
+            if let Some(item) = self.items.iter_mut().find(|item| item.commit == sha) {
+                if item.state != EntryState::Pending {
+                    item.started_at = None;
+                    item.record(EntryState::Pending, Some(reason));
+                    restored.push(item.clone());
+                }
+                continue;
+            }
+            let Some(entry) = entry else {
+                continue;
+            };
+
+            let mut item = QueueItem::new(sha, entry.pr, entry.priority);
+            item.date = entry.queued_at;
+            item.state = EntryState::Released;
+            item.record(EntryState::Pending, Some(reason));
+            restored.push(item.clone());
+            self.insert(item);
+        }
+
+        restored
+    }
+
+    /// Consistency problems in a loaded queue, empty when the queue is sound
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut seen = HashSet::new();
+        let now = Utc::now();
+
+        for (index, item) in self.items.iter().enumerate() {
+            let position = index + 1;
+            if !is_full_sha(&item.commit) {
+                problems.push(format!("#{}: '{}' is not a full 40-character commit SHA", position, item.commit));
+            }
+            if !seen.insert(item.commit.as_str()) {
+                problems.push(format!("#{}: commit {} is queued more than once", position, item.commit));
+            }
+            if item.date > now {
+                problems.push(format!("#{}: commit {} is queued in the future ({})", position, item.commit, item.date.to_rfc3339()));
+            }
+            if item.state.is_terminal() {
+                problems.push(format!("#{}: commit {} is {} but still queued", position, item.commit, item.state));
+            }
+            if item.started_at.is_some_and(|started| started < item.date) {
+                problems.push(format!("#{}: commit {} started before it was queued", position, item.commit));
+            }
+        }
+
+        problems
+    }
+}
+
+/// Result of adding a commit to the queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddOutcome {
+    /// 1-based position of the commit after the operation
+    pub position: usize,
+    /// Total number of queued commits
+    pub len: usize,
+    /// False when the commit was already queued
+    pub added: bool,
+}
+
+/// Summary of a branch queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueStatus {
+    pub count: usize,
+    pub oldest: Option<DateTime<Utc>>,
+}
+
+/// Returned when the queue lock could not be taken within the timeout
+#[derive(Debug)]
+pub struct LockTimeout {
+    pub path: PathBuf,
+    pub waited: Duration,
+}
+
+impl fmt::Display for LockTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Timed out after {:.1}s waiting for release queue lock {}",
+            self.waited.as_secs_f64(), self.path.display()
+        )
+    }
+}
+
+impl std::error::Error for LockTimeout {}
+
+/// Held while the queue directory is locked; the lock is released on drop
+struct QueueLock {
+    _file: File,
+}
+
+/// Entry format of the legacy `queue.json` file
+#[derive(Debug, Deserialize)]
+struct LegacyEntry {
+    sha: String,
+    branch: String,
+    timestamp: i64,
+}
+
+/// Handle on the release queue directory
+#[derive(Debug, Clone)]
+pub struct ReleaseQueue {
+    dir: PathBuf,
+    lock_timeout: Duration,
+}
+
+/// Check whether a branch is allowed to have a release queue
+pub fn is_release_branch(branch: &str) -> bool {
+    RELEASE_BRANCHES.contains(&branch)
+}
+
+impl ReleaseQueue {
+    /// Open the queue stored in `dir`, creating it and migrating legacy data as needed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let queue = Self { dir: dir.into(), lock_timeout: DEFAULT_LOCK_TIMEOUT };
+        fs::create_dir_all(&queue.dir)
+            .with_context(|| format!("Failed to create queue directory {}", queue.dir.display()))?;
+        queue.migrate_legacy()?;
+        Ok(queue)
+    }
+
+    /// Open an existing queue directory without creating it or migrating legacy data
+    ///
+    /// For commands that only inspect the queue, so that pointing them at the
+    /// wrong directory fails instead of creating one.
+    pub fn open_read_only(dir: impl Into<PathBuf>) -> Result<Self> {
+        let queue = Self { dir: dir.into(), lock_timeout: DEFAULT_LOCK_TIMEOUT };
+        if !queue.dir.is_dir() {
+            bail!("Queue directory {} does not exist", queue.dir.display());
+        }
+        Ok(queue)
+    }
+
+    /// Open the queue at its default location
+    ///
+    /// Inside Actions the directory is resolved against `GITHUB_WORKSPACE`, so
+    /// steps that run from `.github/scripts` still find the queue.
+    pub fn open_default() -> Result<Self> {
+        match std::env::var_os("GITHUB_WORKSPACE") {
+            Some(workspace) => Self::open(Path::new(&workspace).join(QUEUE_DIR)),
+            None => Self::open(QUEUE_DIR),
+        }
+    }
+
+    /// Change how long operations wait for the queue lock
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Directory holding the queue files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the queue file for a branch
+    pub fn path_for(&self, branch: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", branch))
+    }
+
+    /// Load the queue for a branch, returning an empty queue if none exists yet
+    pub fn load(&self, branch: &str) -> Result<QueueFile> {
+        check_branch(branch)?;
+        let path = self.path_for(branch);
+
+        if !path.exists() {
+            return Ok(QueueFile::new(branch));
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        if contents.trim().is_empty() {
+            return Ok(QueueFile::new(branch));
+        }
+
+        let mut queue: QueueFile = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        if queue.version > SCHEMA_VERSION {
+            bail!(
+                "{} uses queue schema v{}, but this build only understands up to v{}",
+                path.display(), queue.version, SCHEMA_VERSION
+            );
+        }
+
+        // Files written by older versions carry no header or lanes; fill them in
+        queue.version = SCHEMA_VERSION;
+        queue.branch = branch.to_string();
+        queue.sort();
+
+        Ok(queue)
+    }
+
+    /// Load a branch queue, apply `change` and write the result, all under the queue lock
+    ///
+    /// The queue is only written back when `change` succeeds.
+    pub fn update<T>(&self, branch: &str, change: impl FnOnce(&mut QueueFile) -> Result<T>) -> Result<T> {
+        let _lock = self.lock()?;
+        let mut queue = self.load(branch)?;
+        let result = change(&mut queue)?;
+        self.write_queue(&queue)?;
+        Ok(result)
+    }
+
+    /// Like [`ReleaseQueue::update`], for changes that take entries out of the queue
+    ///
+    /// The history entries returned by `change` are appended to the log after
+    /// the queue has been written, while the lock is still held.
+    fn update_logged<T>(
+        &self,
+        branch: &str,
+        change: impl FnOnce(&mut QueueFile) -> Result<(T, Vec<HistoryEntry>)>,
+    ) -> Result<T> {
+        let _lock = self.lock()?;
+        let mut queue = self.load(branch)?;
+        let (result, entries) = change(&mut queue)?;
+        self.write_queue(&queue)?;
+        history::append(&self.dir, &entries)?;
+        Ok(result)
+    }
+
+    /// Queue a commit in the normal lane; re-adding a queued commit is a no-op
+    pub fn add(&self, branch: &str, sha: &str, pr: Option<u64>) -> Result<AddOutcome> {
+        self.update(branch, |queue| {
+            if let Some(position) = queue.position(sha) {
+                return Ok(AddOutcome { position, len: queue.items.len(), added: false });
+            }
+
+            queue.insert(QueueItem::new(sha, pr, Priority::Normal));
+
+            Ok(AddOutcome { position: position_of(queue, sha), len: queue.items.len(), added: true })
+        })
+    }
+
+    /// Queue a commit in a specific lane, or move an already queued commit there
+    ///
+    /// Anything other than a plain normal-lane enqueue is recorded in the audit trail.
+    pub fn add_with_priority(
+        &self,
+        branch: &str,
+        sha: &str,
+        pr: Option<u64>,
+        request: PriorityRequest<'_>,
+    ) -> Result<AddOutcome> {
+        check_priority_request(&request)?;
+
+        self.update(branch, |queue| {
+            if queue.contains(sha) {
+                queue.reprioritize(sha, request);
+                return Ok(AddOutcome { position: position_of(queue, sha), len: queue.items.len(), added: false });
+            }
+
+            queue.insert(QueueItem::new(sha, pr, request.priority));
+            if request.priority != Priority::Normal {
+                queue.audit.push(AuditEntry {
+                    at: Utc::now(),
+                    actor: request.actor.to_string(),
+                    commit: sha.to_string(),
+                    from: None,
+                    to: request.priority,
+                    reason: request.reason.to_string(),
+                });
+            }
+
+            Ok(AddOutcome { position: position_of(queue, sha), len: queue.items.len(), added: true })
+        })
+    }
+
+    /// Change the lane of a queued commit, returning its new position
+    pub fn set_priority(&self, branch: &str, sha: &str, request: PriorityRequest<'_>) -> Result<Option<usize>> {
+        check_priority_request(&request)?;
+
+        self.update(branch, |queue| {
+            queue.reprioritize(sha, request);
+            Ok(queue.position(sha))
+        })
+    }
+
+    /// Move a queued commit to another position within its lane
+    pub fn move_to(&self, branch: &str, sha: &str, position: usize) -> Result<usize> {
+        self.update(branch, |queue| queue.move_to(sha, position))
+    }
+
+    /// Remove a single commit from a branch queue
+    pub fn remove(&self, branch: &str, sha: &str) -> Result<Option<QueueItem>> {
+        self.update_logged(branch, |queue| {
+            let removed = queue.position(sha).map(|position| queue.items.remove(position - 1));
+            let entries = removed.iter()
+                .map(|item| HistoryEntry::new(branch, item, HistoryEvent::Removed))
+                .collect();
+            Ok((removed, entries))
+        })
+    }
+
+    /// Record that the release pipeline started working on a commit
+    ///
+    /// Returns false when the commit is not queued. Entries that are already
+    /// being built or tested are left as they are.
+    pub fn mark_started(&self, branch: &str, sha: &str) -> Result<bool> {
+        self.update(branch, |queue| {
+            let Some(item) = queue.items.iter_mut().find(|item| item.commit == sha) else {
+                return Ok(false);
+            };
+            if !item.state.is_active() {
+                item.transition(EntryState::Building, None)?;
+            }
+            Ok(true)
+        })
+    }
+
+    /// Move a queued commit to another lifecycle state, returning the updated entry
+    ///
+    /// Releasing a commit drains it together with everything queued before it,
+    /// as [`ReleaseQueue::drain_through`] does, and requires the release `version`
+    /// for the history; cancelled entries leave the queue.
+    pub fn transition(&self, branch: &str, sha: &str, to: EntryState, reason: Option<&str>, version: Option<&str>) -> Result<QueueItem> {
+        self.update_logged(branch, |queue| {
+            let Some(position) = queue.position(sha) else {
+                bail!("Commit {} is not queued for {}", sha, branch);
+            };
+            let item = &mut queue.items[position - 1];
+
+            if to == EntryState::Released {
+                if !item.state.can_transition_to(to) {
+                    return Err(InvalidTransition { commit: sha.to_string(), from: item.state, to }.into());
+                }
+                let Some(version) = version.map(str::trim).filter(|version| !version.is_empty()) else {
+                    bail!("A version is required to mark commit {} as released", sha);
+                };
+                let released = queue.release_through(sha);
+                let entries = released_history(branch, &released, Some(version));
+                return Ok((released.last().cloned().expect("released commit is queued"), entries));
+            }
+
+            item.transition(to, reason)?;
+            let updated = item.clone();
+            let mut entries = Vec::new();
+            if to.is_terminal() {
+                queue.items.remove(position - 1);
+                entries.push(HistoryEntry::new(branch, &updated, HistoryEvent::Cancelled).with_reason(reason));
+            }
+            Ok((updated, entries))
+        })
+    }
+
+    /// Remove a released commit and everything queued before it, returning the removed items
+    ///
+    /// Earlier entries shipped with the release and are marked released without
+    /// going through the intermediate states; failed entries stay queued. If the
+    /// commit was marked as started, its release time is recorded for ETAs.
+    /// The drained entries are logged to the history under `version`.
+    pub fn drain_through(&self, branch: &str, sha: &str, version: Option<&str>) -> Result<Vec<QueueItem>> {
+        self.update_logged(branch, |queue| {
+            let released = queue.release_through(sha);
+            let entries = released_history(branch, &released, version);
+            Ok((released, entries))
+        })
+    }
+
+    /// Return the commits released in `version` to pending, see [`QueueFile::restore_release`]
+    pub fn rollback(&self, branch: &str, version: &str, commit: Option<&str>) -> Result<Vec<QueueItem>> {
+        self.update(branch, |queue| self.restore_into(queue, branch, version, commit))
+    }
+
+    /// The entries [`ReleaseQueue::rollback`] would restore, without changing the queue
+    pub fn rollback_preview(&self, branch: &str, version: &str, commit: Option<&str>) -> Result<Vec<QueueItem>> {
+        let mut queue = self.load(branch)?;
+        self.restore_into(&mut queue, branch, version, commit)
+    }
+
+    fn restore_into(&self, queue: &mut QueueFile, branch: &str, version: &str, commit: Option<&str>) -> Result<Vec<QueueItem>> {
+        let released: Vec<HistoryEntry> = history::read(&self.dir)?
+            .into_iter()
+            .filter(|entry| entry.event == HistoryEvent::Released && entry.branch == branch)
+            .filter(|entry| entry.version.as_deref() == Some(version))
+            .collect();
+        Ok(queue.restore_release(&released, commit, &format!("rolled back {}", version)))
+    }
+
+    /// Drop queued commits that are gone, off-branch or already released
+    ///
+    /// Returns the removed items with the reason each was dropped.
+    pub fn prune(&self, branch: &str, verifier: &CommitVerifier) -> Result<Vec<(QueueItem, Reachability)>> {
+        self.update_logged(branch, |queue| {
+            let mut pruned = Vec::new();
+            let mut kept = Vec::new();
+
+            for item in queue.items.drain(..) {
+                match verifier.reachability(&item.commit, branch)? {
+                    Reachability::Pending => kept.push(item),
+                    reason => pruned.push((item, reason)),
+                }
+            }
+
+            queue.items = kept;
+            let entries = pruned.iter()
+                .map(|(item, reason)| {
+                    HistoryEntry::new(branch, item, HistoryEvent::Pruned).with_reason(Some(&reason.to_string()))
+                })
+                .collect();
+            Ok((pruned, entries))
+        })
+    }
+
+    /// Number of queued commits and the age of the oldest one
+    pub fn status(&self, branch: &str) -> Result<QueueStatus> {
+        let queue = self.load(branch)?;
+
+        Ok(QueueStatus {
+            count: queue.items.len(),
+            oldest: queue.items.iter().map(|item| item.date).min(),
+        })
+    }
+
+    /// Batching policy checked in next to the queue files
+    pub fn policy(&self) -> Result<ReleasePolicy> {
+        ReleasePolicy::load(&self.dir)
+    }
+
+    /// Check a branch queue against its batching policy
+    pub fn readiness(&self, branch: &str) -> Result<Readiness> {
+        let queue = self.load(branch)?;
+        Ok(self.policy()?.for_branch(branch).evaluate(&queue, Utc::now()))
+    }
+
+    /// Estimated wait for the entry at a 1-based position, batched by the branch policy
+    pub fn estimate(&self, branch: &str, position: usize) -> Result<Estimate> {
+        let batch_size = self.policy()?.for_branch(branch).min_items;
+        Ok(self.load(branch)?.estimate(position, batch_size))
+    }
+
+    /// Snapshot of a branch queue and its readiness for rendering
+    pub fn report(&self, branch: &str) -> Result<StatusReport> {
+        let queue = self.load(branch)?;
+        let readiness = self.policy()?.for_branch(branch).evaluate(&queue, Utc::now());
+        Ok(StatusReport::new(queue, readiness))
+    }
+
+    /// Move entries from the legacy `queue.json` into the per-branch files
+    ///
+    /// Returns the number of entries that were added. The legacy file is
+    /// removed once its contents have been written out. Entries of branches
+    /// without a release queue are left in it, with a warning, rather than
+    /// dropped.
+    pub fn migrate_legacy(&self) -> Result<usize> {
+        let legacy_path = self.dir.join(LEGACY_QUEUE_FILE);
+        if !legacy_path.exists() {
+            return Ok(0);
+        }
+
+        let _lock = self.lock()?;
+
+        // Another job may have finished the migration while we waited
+        if !legacy_path.exists() {
+            return Ok(0);
+        }
+
+        let contents = fs::read_to_string(&legacy_path)
+            .with_context(|| format!("Failed to read {}", legacy_path.display()))?;
+        let entries = parse_legacy_entries(&contents)
+            .with_context(|| format!("Failed to parse {}", legacy_path.display()))?;
+
+        // Group by branch, keeping the original order within each branch
+        let mut by_branch: BTreeMap<String, Vec<LegacyEntry>> = BTreeMap::new();
+        let mut unmigrated = Vec::new();
+        for raw in entries {
+            let entry: LegacyEntry = serde_json::from_value(raw.clone())
+                .with_context(|| format!("Failed to parse {}", legacy_path.display()))?;
+            if is_release_branch(&entry.branch) {
+                by_branch.entry(entry.branch.clone()).or_default().push(entry);
+            } else {
+                unmigrated.push(raw);
+            }
+        }
+
+        let mut migrated = 0;
+        for (branch, entries) in by_branch {
+            let mut queue = self.load(&branch)?;
+            for entry in entries {
+                if queue.contains(&entry.sha) {
+                    continue;
+                }
+                let mut item = QueueItem::new(&entry.sha, None, Priority::Normal);
+                item.date = Utc.timestamp_opt(entry.timestamp, 0).single().unwrap_or_else(Utc::now);
+                queue.insert(item);
+                migrated += 1;
+            }
+            self.write_queue(&queue)?;
+        }
+
+        if unmigrated.is_empty() {
+            fs::remove_file(&legacy_path)
+                .with_context(|| format!("Failed to remove {}", legacy_path.display()))?;
+        } else {
+            let mut json = serde_json::to_string_pretty(&unmigrated)
+                .context("Failed to serialize legacy queue entries")?;
+            json.push('\n');
+            write_atomic(&legacy_path, json.as_bytes())?;
+            crate::github::warning(&format!(
+                "{} entr{} in {} belong to branches without a release queue and were left in place",
+                unmigrated.len(),
+                if unmigrated.len() == 1 { "y" } else { "ies" },
+                legacy_path.display()
+            ));
+        }
+
+        Ok(migrated)
+    }
+
+    /// Take the advisory lock on the queue directory, waiting up to the lock timeout
+    fn lock(&self) -> Result<QueueLock> {
+        let path = self.dir.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(QueueLock { _file: file }),
+                Err(TryLockError::WouldBlock) => {
+                    if started.elapsed() >= self.lock_timeout {
+                        return Err(LockTimeout { path, waited: started.elapsed() }.into());
+                    }
+                    thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+                }
+            }
+        }
+    }
+
+    /// Atomically replace a branch queue file; callers must hold the queue lock
+    fn write_queue(&self, queue: &QueueFile) -> Result<()> {
+        check_branch(&queue.branch)?;
+        let path = self.path_for(&queue.branch);
+
+        let mut json = serde_json::to_string_pretty(queue)
+            .context("Failed to serialize queue")?;
+        json.push('\n');
+
+        write_atomic(&path, json.as_bytes())
+    }
+}
+
+/// Write to a temporary sibling file, flush it to disk, then rename it over `path`
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let file_name = path.file_name()
+        .with_context(|| format!("Invalid queue file path {}", path.display()))?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// History lines for entries shipped in a release
+fn released_history(branch: &str, released: &[QueueItem], version: Option<&str>) -> Vec<HistoryEntry> {
+    released
+        .iter()
+        .map(|item| HistoryEntry::new(branch, item, HistoryEvent::Released).with_version(version))
+        .collect()
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// Check that a string is a full, lowercase hex commit SHA
+pub fn is_full_sha(sha: &str) -> bool {
+    sha.len() == 40 && sha.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Reject queue operations on branches that do not release
+fn check_branch(branch: &str) -> Result<()> {
+    if !is_release_branch(branch) {
+        bail!("Invalid branch '{}': release queues exist only for {}", branch, RELEASE_BRANCHES.join("/"));
+    }
+    Ok(())
+}
+
+/// Bypassing the normal lane must come with an explanation
+fn check_priority_request(request: &PriorityRequest<'_>) -> Result<()> {
+    if request.priority == Priority::Hotfix && request.reason.trim().is_empty() {
+        bail!("A reason is required to queue a commit as a hotfix");
+    }
+    Ok(())
+}
+
+/// Position of a commit that is known to be queued
+fn position_of(queue: &QueueFile, sha: &str) -> usize {
+    queue.position(sha).unwrap_or(queue.items.len())
+}
+
+/// Parse the legacy queue, which was either a JSON array or one entry per line
+fn parse_legacy_entries(contents: &str) -> Result<Vec<serde_json::Value>> {
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if let Ok(entries) = serde_json::from_str::<Vec<serde_json::Value>>(contents) {
+        return Ok(entries);
+    }
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(Into::into))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const SHA_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    const SHA_C: &str = "cccccccccccccccccccccccccccccccccccccccc";
+
+    #[test]
+    fn test_add_is_idempotent_and_ordered() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+
+        assert!(queue.add("beta", SHA_A, Some(1)).unwrap().added);
+        assert_eq!(queue.add("beta", SHA_B, None).unwrap().position, 2);
+
+        let again = queue.add("beta", SHA_A, None).unwrap();
+        assert!(!again.added);
+        assert_eq!(again.position, 1);
+
+        let file = queue.load("beta").unwrap();
+        assert_eq!(file.version, SCHEMA_VERSION);
+        assert_eq!(file.items.len(), 2);
+        assert_eq!(file.items[0].pr, Some(1));
+    }
+
+    #[test]
+    fn test_drain_through_removes_older_items() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        for sha in [SHA_A, SHA_B, SHA_C] {
+            queue.add("main", sha, None).unwrap();
+        }
+
+        let drained = queue.drain_through("main", SHA_B, None).unwrap();
+        assert_eq!(drained.len(), 2);
+        assert_eq!(queue.status("main").unwrap().count, 1);
+        assert!(queue.drain_through("main", SHA_A, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rollback_restores_released_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        for sha in [SHA_A, SHA_B, SHA_C] {
+            queue.add("beta", sha, Some(7)).unwrap();
+        }
+        queue.drain_through("beta", SHA_B, Some("beta-v1.2.3")).unwrap();
+        queue.mark_started("beta", SHA_C).unwrap();
+
+        let preview = queue.rollback_preview("beta", "beta-v1.2.3", None).unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(queue.status("beta").unwrap().count, 1);
+
+        let restored = queue.rollback("beta", "beta-v1.2.3", Some(SHA_C)).unwrap();
+        assert_eq!(restored.len(), 3);
+        let file = queue.load("beta").unwrap();
+        assert_eq!(file.items.iter().map(|item| item.commit.as_str()).collect::<Vec<_>>(), [SHA_A, SHA_B, SHA_C]);
+        assert!(file.items.iter().all(|item| item.state == EntryState::Pending && item.started_at.is_none()));
+        assert_eq!(file.items[0].pr, Some(7));
+        assert_eq!(file.items[0].transitions.last().unwrap().reason.as_deref(), Some("rolled back beta-v1.2.3"));
+        assert!(file.problems().is_empty());
+
+        assert!(queue.rollback("beta", "beta-v1.2.3", Some(SHA_C)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reads_unversioned_branch_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("beta.json"),
+            format!(r#"{{"items":[{{"commit":"{}","date":"2024-01-01T00:00:00Z"}}]}}"#, SHA_A),
+        ).unwrap();
+
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        let file = queue.load("beta").unwrap();
+        assert_eq!(file.branch, "beta");
+        assert_eq!(file.items[0].commit, SHA_A);
+        assert_eq!(file.items[0].pr, None);
+    }
+
+    #[test]
+    fn test_migrates_legacy_queue_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let legacy = format!(
+            r#"[
+                {{"sha":"{a}","branch":"beta","timestamp":1700000000,"status":"pending","estimated_time":""}},
+                {{"sha":"{b}","branch":"main","timestamp":1700000100,"status":"pending","estimated_time":""}},
+                {{"sha":"{c}","branch":"develop","timestamp":1700000200,"status":"pending","estimated_time":""}}
+            ]"#,
+            a = SHA_A, b = SHA_B, c = SHA_C
+        );
+        fs::write(dir.path().join(LEGACY_QUEUE_FILE), legacy).unwrap();
+
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        assert_eq!(queue.load("beta").unwrap().items[0].commit, SHA_A);
+        assert_eq!(queue.load("main").unwrap().items[0].commit, SHA_B);
+
+        // Branches without a queue stay in the legacy file instead of being lost
+        let left: Vec<serde_json::Value> = serde_json::from_str(&fs::read_to_string(dir.path().join(LEGACY_QUEUE_FILE)).unwrap()).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0]["sha"], SHA_C);
+        assert_eq!(left[0]["status"], "pending");
+        assert_eq!(ReleaseQueue::open(dir.path()).unwrap().migrate_legacy().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_open_read_only_leaves_directory_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ReleaseQueue::open_read_only(dir.path().join("missing")).is_err());
+        assert!(!dir.path().join("missing").exists());
+
+        let legacy = format!(r#"[{{"sha":"{}","branch":"beta","timestamp":1700000000}}]"#, SHA_A);
+        fs::write(dir.path().join(LEGACY_QUEUE_FILE), &legacy).unwrap();
+
+        let queue = ReleaseQueue::open_read_only(dir.path()).unwrap();
+        assert!(queue.load("beta").unwrap().items.is_empty());
+        assert_eq!(fs::read_to_string(dir.path().join(LEGACY_QUEUE_FILE)).unwrap(), legacy);
+        assert!(!queue.path_for("beta").exists());
+    }
+
+    #[test]
+    fn test_drain_records_throughput_for_started_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        for sha in [SHA_A, SHA_B, SHA_C] {
+            queue.add("beta", sha, None).unwrap();
+        }
+
+        // Unstarted entries leave no sample behind
+        queue.drain_through("beta", SHA_A, None).unwrap();
+        assert!(queue.load("beta").unwrap().throughput.is_empty());
+
+        assert!(queue.mark_started("beta", SHA_B).unwrap());
+        assert!(!queue.mark_started("beta", SHA_A).unwrap());
+        queue.drain_through("beta", SHA_B, None).unwrap();
+
+        let file = queue.load("beta").unwrap();
+        assert_eq!(file.throughput.len(), 1);
+        assert_eq!(file.throughput[0].commit, SHA_B);
+        assert_eq!(file.estimate(2, 1).samples, 1);
+    }
+
+    #[test]
+    fn test_failed_release_stays_queued_until_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        for sha in [SHA_A, SHA_B, SHA_C] {
+            queue.add("main", sha, None).unwrap();
+        }
+
+        queue.transition("main", SHA_A, EntryState::Building, None, None).unwrap();
+        assert!(queue.transition("main", SHA_A, EntryState::Failed, Some(" "), None).is_err());
+        let failed = queue.transition("main", SHA_A, EntryState::Failed, Some("smoke tests"), None).unwrap();
+        assert_eq!(failed.failure_reason(), Some("smoke tests"));
+
+        // Releasing a later commit leaves the failed one behind
+        let drained = queue.drain_through("main", SHA_B, None).unwrap();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[0].state, EntryState::Released);
+        assert_eq!(queue.load("main").unwrap().items[0].commit, SHA_A);
+
+        let err = queue.transition("main", SHA_A, EntryState::Testing, None, None).unwrap_err();
+        assert!(err.downcast_ref::<InvalidTransition>().is_some());
+
+        let retried = queue.transition("main", SHA_A, EntryState::Pending, None, None).unwrap();
+        assert_eq!(retried.retries, 1);
+        assert_eq!(retried.started_at, None);
+        assert_eq!(retried.transitions.len(), 3);
+
+        queue.transition("main", SHA_C, EntryState::Cancelled, Some("superseded"), None).unwrap();
+        assert!(!queue.load("main").unwrap().contains(SHA_C));
+
+        // Released entries are logged under the release that shipped them
+        queue.transition("main", SHA_A, EntryState::Building, None, None).unwrap();
+        queue.transition("main", SHA_A, EntryState::Testing, None, None).unwrap();
+        assert!(queue.transition("main", SHA_A, EntryState::Released, None, None).is_err());
+        queue.transition("main", SHA_A, EntryState::Released, None, Some("v1.3.0")).unwrap();
+        let released = history::read(dir.path()).unwrap();
+        let released = released.iter().find(|entry| entry.event == HistoryEvent::Released && entry.commit == SHA_A);
+        assert_eq!(released.and_then(|entry| entry.version.as_deref()), Some("v1.3.0"));
+    }
+
+    #[test]
+    fn test_departures_are_logged_to_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        for sha in [SHA_A, SHA_B, SHA_C] {
+            queue.add("beta", sha, Some(1)).unwrap();
+        }
+
+        queue.remove("beta", SHA_C).unwrap();
+        queue.drain_through("beta", SHA_B, Some("beta-v0.2.0")).unwrap();
+
+        let entries = history::read(dir.path()).unwrap();
+        let events: Vec<_> = entries.iter().map(|entry| (entry.event, entry.commit.as_str())).collect();
+        assert_eq!(events, [
+            (HistoryEvent::Removed, SHA_C),
+            (HistoryEvent::Released, SHA_A),
+            (HistoryEvent::Released, SHA_B),
+        ]);
+        assert_eq!(entries[2].version.as_deref(), Some("beta-v0.2.0"));
+    }
+
+    #[test]
+    fn test_hotfix_jumps_ahead_and_is_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        queue.add("beta", SHA_A, None).unwrap();
+        queue.add_with_priority("beta", SHA_B, None, PriorityRequest {
+            priority: Priority::Low, actor: "alice", reason: "docs only",
+        }).unwrap();
+
+        let hotfix = PriorityRequest { priority: Priority::Hotfix, actor: "bob", reason: "CVE fix" };
+        let outcome = queue.add_with_priority("beta", SHA_C, Some(7), hotfix).unwrap();
+        assert_eq!(outcome.position, 1);
+
+        let file = queue.load("beta").unwrap();
+        let order: Vec<&str> = file.items.iter().map(|item| item.commit.as_str()).collect();
+        assert_eq!(order, [SHA_C, SHA_A, SHA_B]);
+        assert_eq!(file.audit.len(), 2);
+        assert_eq!(file.audit[1].actor, "bob");
+        assert_eq!(file.audit[1].from, None);
+
+        // Bumping an existing commit records the previous lane and keeps its
+        // original queue time, so it lands ahead of the later hotfix
+        let bump = PriorityRequest { priority: Priority::Hotfix, actor: "carol", reason: "needed today" };
+        assert_eq!(queue.set_priority("beta", SHA_B, bump).unwrap(), Some(1));
+        let file = queue.load("beta").unwrap();
+        assert_eq!(file.audit.last().unwrap().from, Some(Priority::Low));
+    }
+
+    #[test]
+    fn test_hotfix_requires_reason() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        let request = PriorityRequest { priority: Priority::Hotfix, actor: "bob", reason: " " };
+        assert!(queue.add_with_priority("main", SHA_A, None, request).is_err());
+        assert_eq!("LOW".parse::<Priority>().unwrap(), Priority::Low);
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn test_concurrent_adds_lose_no_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        ReleaseQueue::open(dir.path()).unwrap();
+
+        let threads = 8;
+        let per_thread = 25;
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                let path = dir.path().to_path_buf();
+                thread::spawn(move || {
+                    // Each thread opens its own handle, like separate jobs would
+                    let queue = ReleaseQueue::open(path).unwrap();
+                    for i in 0..per_thread {
+                        let sha = format!("{:040x}", t * 1000 + i);
+                        queue.add("beta", &sha, None).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        assert_eq!(queue.status("beta").unwrap().count, threads * per_thread);
+
+        // No temporary files may be left behind
+        let leftovers: Vec<_> = fs::read_dir(dir.path()).unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn test_lock_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap()
+            .with_lock_timeout(Duration::from_millis(100));
+
+        let _held = queue.lock().unwrap();
+        let err = queue.add("beta", SHA_A, None).unwrap_err();
+        assert!(err.downcast_ref::<LockTimeout>().is_some());
+    }
+
+    #[test]
+    fn test_move_stays_within_lane() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        for sha in [SHA_A, SHA_B, SHA_C] {
+            queue.add("beta", sha, None).unwrap();
+        }
+
+        assert_eq!(queue.move_to("beta", SHA_C, 1).unwrap(), 1);
+        let order: Vec<_> = queue.load("beta").unwrap().items.into_iter().map(|item| item.commit).collect();
+        assert_eq!(order, [SHA_C, SHA_A, SHA_B]);
+
+        queue.move_to("beta", SHA_C, 3).unwrap();
+        assert_eq!(queue.load("beta").unwrap().position(SHA_C), Some(3));
+
+        // Moving keeps the queue time the age thresholds are based on
+        queue.move_to("beta", SHA_A, 2).unwrap();
+        let file = queue.load("beta").unwrap();
+        let order: Vec<_> = file.items.iter().map(|item| (item.commit.as_str(), item.sequence)).collect();
+        assert_eq!(order, [(SHA_B, 1), (SHA_A, 2), (SHA_C, 3)]);
+        assert!(file.items[0].date > file.items[1].date);
+
+        let request = PriorityRequest { priority: Priority::Low, actor: "tester", reason: "" };
+        queue.set_priority("beta", SHA_A, request).unwrap();
+        assert!(queue.move_to("beta", SHA_A, 1).is_err());
+    }
+
+    #[test]
+    fn test_problems_reports_bad_entries() {
+        let mut file = QueueFile::new("beta");
+        for commit in [SHA_A, "abc123", SHA_A] {
+            let mut item = QueueItem::new(commit, None, Priority::Normal);
+            item.date = Utc::now() - chrono::Duration::minutes(1);
+            file.items.push(item);
+        }
+
+        let problems = file.problems();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("abc123"));
+        assert!(problems[1].contains("more than once"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_branch_and_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(dir.path()).unwrap();
+        assert!(queue.load("develop").is_err());
+
+        fs::write(dir.path().join("beta.json"), r#"{"version":99,"items":[]}"#).unwrap();
+        assert!(queue.load("beta").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Git checks for queued commits
+//!
+//! A commit may only be queued for a branch when it is a full SHA that the
+//! local clone can resolve and that is already part of the branch history.
+//! The same checks let `prune` drop entries that were force-pushed away or
+//! that a published release tag already covers.
+
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::is_full_sha;
+
+/// State of a queued commit relative to its release branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reachability {
+    /// Part of the branch and not released yet
+    Pending,
+    /// The clone does not know the commit
+    Missing,
+    /// The commit exists but is not an ancestor of the branch
+    NotOnBranch,
+    /// A release tag already contains the commit
+    Released { tag: String },
+}
+
+impl fmt::Display for Reachability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reachability::Pending => write!(f, "pending"),
+            Reachability::Missing => write!(f, "commit does not exist"),
+            Reachability::NotOnBranch => write!(f, "not reachable from the release branch"),
+            Reachability::Released { tag } => write!(f, "already released in {}", tag),
+        }
+    }
+}
+
+/// Prefix of the release tags cut from a branch
+pub fn release_tag_prefix(branch: &str) -> &'static str {
+    match branch {
+        "main" => "stable-v",
+        _ => "beta-v",
+    }
+}
+
+/// Runs commit checks against a local clone
+#[derive(Debug, Clone)]
+pub struct CommitVerifier {
+    repo: PathBuf,
+}
+
+impl CommitVerifier {
+    /// Check commits in the clone containing `repo`
+    pub fn new(repo: impl Into<PathBuf>) -> Self {
+        Self { repo: repo.into() }
+    }
+
+    /// Check commits in the clone containing the working directory
+    pub fn current_dir() -> Self {
+        Self::new(".")
+    }
+
+    /// Make sure a commit may be queued for a branch
+    pub fn verify_queueable(&self, sha: &str, branch: &str) -> Result<()> {
+        if !is_full_sha(sha) {
+            bail!("'{}' is not a full 40-character commit SHA", sha);
+        }
+
+        match self.reachability(sha, branch)? {
+            Reachability::Pending => Ok(()),
+            Reachability::Released { tag } => bail!("Commit {} is already released in {}", sha, tag),
+            Reachability::Missing => bail!("Commit {} does not exist in this repository", sha),
+            Reachability::NotOnBranch => bail!("Commit {} is not reachable from {}", sha, self.branch_ref(branch)?),
+        }
+    }
+
+    /// Classify a queued commit against its release branch
+    pub fn reachability(&self, sha: &str, branch: &str) -> Result<Reachability> {
+        if !is_full_sha(sha) || !self.succeeds(&["cat-file", "-e", &format!("{}^{{commit}}", sha)])? {
+            return Ok(Reachability::Missing);
+        }
+
+        let branch_ref = self.branch_ref(branch)?;
+        if !self.succeeds(&["merge-base", "--is-ancestor", sha, &branch_ref])? {
+            return Ok(Reachability::NotOnBranch);
+        }
+
+        let pattern = format!("{}*", release_tag_prefix(branch));
+        let tags = self.output(&["tag", "--contains", sha, "--list", &pattern, "--sort=creatordate"])?;
+        match tags.lines().next() {
+            Some(tag) => Ok(Reachability::Released { tag: tag.to_string() }),
+            None => Ok(Reachability::Pending),
+        }
+    }
+
+    /// Ref to check ancestry against, preferring the remote-tracking branch
+    fn branch_ref(&self, branch: &str) -> Result<String> {
+        for candidate in [format!("refs/remotes/origin/{}", branch), format!("refs/heads/{}", branch)] {
+            if self.succeeds(&["rev-parse", "--verify", "--quiet", &candidate])? {
+                return Ok(candidate);
+            }
+        }
+        bail!("Branch {} is not available in this clone; fetch it before checking commits", branch)
+    }
+
+    fn git(&self, args: &[&str]) -> Command {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.repo).args(args);
+        command
+    }
+
+    fn succeeds(&self, args: &[&str]) -> Result<bool> {
+        let output = self.git(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+        Ok(output.status.success())
+    }
+
+    fn output(&self, args: &[&str]) -> Result<String> {
+        let output = self.git(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+        if !output.status.success() {
+            bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release_queue::ReleaseQueue;
+    use crate::test_support::git;
+    use std::path::Path;
+
+    fn commit(dir: &Path, message: &str) -> String {
+        git(dir, &["commit", "-q", "--allow-empty", "--no-gpg-sign", "-m", message]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn test_reachability_against_branch_and_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q", "-b", "beta"]);
+        let released = commit(repo, "released");
+        git(repo, &["tag", "beta-v0.1.0"]);
+        let pending = commit(repo, "pending");
+        git(repo, &["checkout", "-q", "-b", "feature"]);
+        let stray = commit(repo, "stray");
+        git(repo, &["checkout", "-q", "beta"]);
+
+        let verifier = CommitVerifier::new(repo);
+        assert_eq!(verifier.reachability(&pending, "beta").unwrap(), Reachability::Pending);
+        assert_eq!(
+            verifier.reachability(&released, "beta").unwrap(),
+            Reachability::Released { tag: "beta-v0.1.0".to_string() }
+        );
+        assert_eq!(verifier.reachability(&stray, "beta").unwrap(), Reachability::NotOnBranch);
+        assert_eq!(verifier.reachability(&"f".repeat(40), "beta").unwrap(), Reachability::Missing);
+
+        assert!(verifier.verify_queueable(&pending, "beta").is_ok());
+        assert!(verifier.verify_queueable(&released, "beta").is_err());
+        assert!(verifier.verify_queueable(&pending[..12], "beta").is_err());
+        assert!(verifier.verify_queueable(&stray, "beta").is_err());
+        assert!(verifier.verify_queueable(&pending, "main").is_err());
+
+        let queue_dir = tempfile::tempdir().unwrap();
+        let queue = ReleaseQueue::open(queue_dir.path()).unwrap();
+        for sha in [&released, &pending, &stray] {
+            queue.add("beta", sha, None).unwrap();
+        }
+        let pruned = queue.prune("beta", &verifier).unwrap();
+        assert_eq!(pruned.len(), 2);
+        assert_eq!(queue.load("beta").unwrap().items[0].commit, pending);
+    }
+}
@@ -0,0 +1,173 @@
+//! Release ETA estimation from recorded queue throughput
+//!
+//! Each processed queue entry leaves a [`ReleaseSample`] with the time its
+//! release started and finished. Estimates use the median of the most recent
+//! samples for the branch, with the interquartile range as a confidence band.
+//! Commits are released in batches of the policy's `min_items`, so the wait
+//! grows with the number of batches ahead rather than the entries ahead.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Per-release duration assumed until a branch has recorded history
+pub const DEFAULT_RELEASE_DURATION: Duration = Duration::from_secs(15 * 60);
+
+/// Number of recent releases kept per branch for estimation
+pub const ETA_WINDOW: usize = 20;
+
+/// Timing of one processed queue entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseSample {
+    pub commit: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl ReleaseSample {
+    /// Wall-clock time the release took; clock skew is treated as zero
+    pub fn duration(&self) -> Duration {
+        (self.finished_at - self.started_at).to_std().unwrap_or_default()
+    }
+}
+
+/// Expected wait for a queue position with its confidence band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub expected: Duration,
+    pub low: Duration,
+    pub high: Duration,
+    /// Number of samples the estimate is based on; 0 means the default was used
+    pub samples: usize,
+}
+
+impl Estimate {
+    /// Estimate how long until the entry at a 1-based `position` is released
+    /// when `batch_size` commits ship per release
+    pub fn for_position(samples: &[ReleaseSample], position: usize, batch_size: usize) -> Self {
+        let ahead = (position.saturating_sub(1) / batch_size.max(1)) as u32;
+        let recent = &samples[samples.len().saturating_sub(ETA_WINDOW)..];
+
+        let mut durations: Vec<Duration> = recent.iter().map(ReleaseSample::duration).collect();
+        durations.sort();
+
+        if durations.is_empty() {
+            let expected = DEFAULT_RELEASE_DURATION * ahead;
+            return Self { expected, low: expected, high: expected, samples: 0 };
+        }
+
+        Self {
+            expected: percentile(&durations, 50) * ahead,
+            low: percentile(&durations, 25) * ahead,
+            high: percentile(&durations, 75) * ahead,
+            samples: durations.len(),
+        }
+    }
+
+    /// Human-readable form used for the `estimated_time` output
+    pub fn describe(&self) -> String {
+        if self.expected.is_zero() && self.high.is_zero() {
+            return "Next in queue".to_string();
+        }
+
+        let expected = format_duration(self.expected);
+        if self.samples == 0 {
+            return format!("~{} (default estimate, no release history yet)", expected);
+        }
+
+        format!(
+            "~{} (likely {} to {}, based on {} release{})",
+            expected,
+            format_duration(self.low),
+            format_duration(self.high),
+            self.samples,
+            if self.samples == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Nearest-rank percentile of a sorted, non-empty slice
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Formats a duration into a human-readable time string, rounded to minutes
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = (duration.as_secs() + 30) / 60;
+
+    if minutes < 60 {
+        return format!("{} minute{}", minutes, if minutes == 1 { "" } else { "s" });
+    }
+
+    let hours = minutes / 60;
+    let remaining_minutes = minutes % 60;
+
+    if remaining_minutes == 0 {
+        return format!("{} hour{}", hours, if hours > 1 { "s" } else { "" });
+    }
+
+    format!("{} hour{} {} minute{}",
+        hours,
+        if hours > 1 { "s" } else { "" },
+        remaining_minutes,
+        if remaining_minutes > 1 { "s" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample(minutes: i64) -> ReleaseSample {
+        let started_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        ReleaseSample {
+            commit: "a".repeat(40),
+            started_at,
+            finished_at: started_at + chrono::Duration::minutes(minutes),
+        }
+    }
+
+    #[test]
+    fn test_defaults_without_history() {
+        let estimate = Estimate::for_position(&[], 3, 1);
+        assert_eq!(estimate.expected, DEFAULT_RELEASE_DURATION * 2);
+        assert_eq!(estimate.samples, 0);
+        assert_eq!(Estimate::for_position(&[], 1, 1).describe(), "Next in queue");
+    }
+
+    #[test]
+    fn test_median_and_range_from_history() {
+        let samples: Vec<_> = [10, 20, 30, 40, 200].into_iter().map(sample).collect();
+        let estimate = Estimate::for_position(&samples, 3, 1);
+
+        assert_eq!(estimate.expected, Duration::from_secs(60 * 60));
+        assert_eq!(estimate.low, Duration::from_secs(40 * 60));
+        assert_eq!(estimate.high, Duration::from_secs(80 * 60));
+        assert_eq!(
+            estimate.describe(),
+            "~1 hour (likely 40 minutes to 1 hour 20 minutes, based on 5 releases)"
+        );
+    }
+
+    #[test]
+    fn test_only_recent_window_is_used() {
+        let mut samples: Vec<_> = (0..ETA_WINDOW).map(|_| sample(500)).collect();
+        samples.extend((0..ETA_WINDOW).map(|_| sample(10)));
+
+        let estimate = Estimate::for_position(&samples, 2, 1);
+        assert_eq!(estimate.expected, Duration::from_secs(10 * 60));
+        assert_eq!(estimate.samples, ETA_WINDOW);
+    }
+
+    #[test]
+    fn test_counts_batches_ahead() {
+        let samples: Vec<_> = [30].into_iter().map(sample).collect();
+
+        // The first batch of ten ships together, the eleventh commit waits for the next one
+        assert_eq!(Estimate::for_position(&samples, 10, 10).describe(), "Next in queue");
+        assert_eq!(Estimate::for_position(&samples, 11, 10).expected, Duration::from_secs(30 * 60));
+        assert_eq!(Estimate::for_position(&samples, 25, 10).expected, Duration::from_secs(60 * 60));
+    }
+}
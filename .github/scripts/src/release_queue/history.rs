@@ -0,0 +1,208 @@
+//! Append-only record of entries that left the queue
+//!
+//! Whenever a commit leaves a branch queue (released, cancelled, removed or
+//! pruned) a line is appended to `.github/release_queue/history.jsonl`. The
+//! released entries of a version double as the source for its release notes,
+//! see [`render_summary`].
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use super::{Priority, QueueItem};
+
+/// File name of the history log inside the queue directory
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// Why an entry left the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryEvent {
+    Released,
+    Cancelled,
+    Removed,
+    Pruned,
+}
+
+/// One line of the history log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When the entry left the queue
+    pub at: DateTime<Utc>,
+    pub event: HistoryEvent,
+    pub branch: String,
+    pub commit: String,
+    #[serde(default)]
+    pub pr: Option<u64>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// When the commit entered the queue
+    pub queued_at: DateTime<Utc>,
+    /// Version tag of the release that shipped the commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Describe a queue item leaving `branch`
+    pub fn new(branch: &str, item: &QueueItem, event: HistoryEvent) -> Self {
+        Self {
+            at: Utc::now(),
+            event,
+            branch: branch.to_string(),
+            commit: item.commit.clone(),
+            pr: item.pr,
+            priority: item.priority,
+            queued_at: item.date,
+            version: None,
+            reason: None,
+        }
+    }
+
+    pub fn with_version(mut self, version: Option<&str>) -> Self {
+        self.version = version.map(str::to_string);
+        self
+    }
+
+    pub fn with_reason(mut self, reason: Option<&str>) -> Self {
+        self.reason = reason.map(str::to_string);
+        self
+    }
+}
+
+/// Append entries to the history log in `dir`
+///
+/// Callers hold the queue lock, so lines from concurrent jobs never interleave.
+pub fn append(dir: &Path, entries: &[HistoryEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let path = dir.join(HISTORY_FILE);
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(lines.as_bytes())
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("Failed to append to {}", path.display()))
+}
+
+/// Read the whole history log in `dir`; a missing log is empty
+pub fn read(dir: &Path) -> Result<Vec<HistoryEntry>> {
+    let path = dir.join(HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse {} line {}", path.display(), index + 1))
+        })
+        .collect()
+}
+
+/// Render the commits released in `version` as a Markdown summary
+///
+/// Returns `None` when the history has no released entries for the version.
+pub fn render_summary(entries: &[HistoryEntry], version: &str) -> Option<String> {
+    let released: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|entry| entry.event == HistoryEvent::Released && entry.version.as_deref() == Some(version))
+        .collect();
+    let first = released.first()?;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "## {}", version);
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "Released from `{}` on {} with {} queued commit{}.",
+        first.branch,
+        first.at.format("%Y-%m-%d"),
+        released.len(),
+        if released.len() == 1 { "" } else { "s" }
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(out, "| Commit | Pull request | Priority | Queued |");
+    let _ = writeln!(out, "|--------|--------------|----------|--------|");
+    for entry in &released {
+        let _ = writeln!(
+            out,
+            "| `{}` | {} | {} | {} |",
+            entry.commit.chars().take(7).collect::<String>(),
+            entry.pr.map(|pr| format!("#{}", pr)).unwrap_or_else(|| "-".to_string()),
+            entry.priority,
+            entry.queued_at.format("%Y-%m-%d")
+        );
+    }
+
+    let mut prs: Vec<u64> = released.iter().filter_map(|entry| entry.pr).collect();
+    prs.sort_unstable();
+    prs.dedup();
+    if !prs.is_empty() {
+        let list: Vec<String> = prs.iter().map(|pr| format!("#{}", pr)).collect();
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Pull requests: {}", list.join(", "));
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(commit: char, pr: Option<u64>) -> QueueItem {
+        QueueItem::new(&commit.to_string().repeat(40), pr, Priority::Normal)
+    }
+
+    #[test]
+    fn test_append_and_render_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        append(dir.path(), &[
+            HistoryEntry::new("beta", &item('a', Some(12)), HistoryEvent::Released).with_version(Some("beta-v1.2.0")),
+            HistoryEntry::new("beta", &item('b', None), HistoryEvent::Released).with_version(Some("beta-v1.2.0")),
+        ]).unwrap();
+        append(dir.path(), &[
+            HistoryEntry::new("beta", &item('c', Some(7)), HistoryEvent::Cancelled).with_reason(Some("superseded")),
+        ]).unwrap();
+
+        let entries = read(dir.path()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].reason.as_deref(), Some("superseded"));
+
+        let summary = render_summary(&entries, "beta-v1.2.0").unwrap();
+        assert!(summary.starts_with("## beta-v1.2.0\n"));
+        assert!(summary.contains("with 2 queued commits."));
+        assert!(summary.contains("| `aaaaaaa` | #12 | normal |"));
+        assert!(summary.contains("| `bbbbbbb` | - | normal |"));
+        assert!(summary.ends_with("Pull requests: #12\n"));
+        assert!(render_summary(&entries, "beta-v9.9.9").is_none());
+
+        // Hand-edited history is not hex-validated
+        let edited = HistoryEntry::new("beta", &QueueItem::new("äöüäöüäöü", None, Priority::Normal), HistoryEvent::Released)
+            .with_version(Some("beta-v1.3.0"));
+        assert!(render_summary(&[edited], "beta-v1.3.0").unwrap().contains("| `äöüäöüä` |"));
+    }
+}
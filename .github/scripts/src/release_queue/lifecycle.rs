@@ -0,0 +1,146 @@
+//! Lifecycle of a queued release
+//!
+//! Every queue entry moves through a fixed set of states:
+//!
+//! ```text
+//! pending -> building -> testing -> released
+//!               |           |
+//!               +-----------+-----> failed -> pending (retry)
+//!
+//! any non-terminal state -> cancelled
+//! ```
+//!
+//! Failed entries stay in the queue until they are retried or cancelled, so a
+//! broken release never disappears without a trace. Each transition is stamped
+//! and kept on the entry.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a queued release currently is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryState {
+    #[default]
+    Pending,
+    Building,
+    Testing,
+    Released,
+    Failed,
+    Cancelled,
+}
+
+impl EntryState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryState::Pending => "pending",
+            EntryState::Building => "building",
+            EntryState::Testing => "testing",
+            EntryState::Released => "released",
+            EntryState::Failed => "failed",
+            EntryState::Cancelled => "cancelled",
+        }
+    }
+
+    /// Terminal entries leave the queue
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, EntryState::Released | EntryState::Cancelled)
+    }
+
+    /// Whether the release pipeline is currently working on the entry
+    pub fn is_active(&self) -> bool {
+        matches!(self, EntryState::Building | EntryState::Testing)
+    }
+
+    /// Check whether moving from this state to `next` is allowed
+    pub fn can_transition_to(&self, next: EntryState) -> bool {
+        use EntryState::*;
+
+        match (self, next) {
+            (Pending, Building)
+            | (Building, Testing)
+            | (Testing, Released)
+            | (Building | Testing, Failed)
+            | (Failed, Pending) => true,
+            (from, Cancelled) => !from.is_terminal(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for EntryState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EntryState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "pending" => Ok(EntryState::Pending),
+            "building" => Ok(EntryState::Building),
+            "testing" => Ok(EntryState::Testing),
+            "released" => Ok(EntryState::Released),
+            "failed" => Ok(EntryState::Failed),
+            "cancelled" | "canceled" => Ok(EntryState::Cancelled),
+            other => Err(anyhow!(
+                "Unknown entry state '{}': expected pending, building, testing, released, failed or cancelled",
+                other
+            )),
+        }
+    }
+}
+
+/// A recorded state change of a queue entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: EntryState,
+    pub to: EntryState,
+    pub at: DateTime<Utc>,
+    /// Why the change happened; always set for failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Returned when a requested state change is not part of the lifecycle
+#[derive(Debug)]
+pub struct InvalidTransition {
+    pub commit: String,
+    pub from: EntryState,
+    pub to: EntryState,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Commit {} cannot move from {} to {}", self.commit, self.from, self.to)
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EntryState::*;
+
+    #[test]
+    fn test_transition_table() {
+        assert!(Pending.can_transition_to(Building));
+        assert!(Building.can_transition_to(Testing));
+        assert!(Testing.can_transition_to(Released));
+        assert!(Building.can_transition_to(Failed));
+        assert!(Failed.can_transition_to(Pending));
+        assert!(Failed.can_transition_to(Cancelled));
+
+        assert!(!Pending.can_transition_to(Released));
+        assert!(!Pending.can_transition_to(Failed));
+        assert!(!Released.can_transition_to(Cancelled));
+        assert!(!Cancelled.can_transition_to(Pending));
+        assert!(!Testing.can_transition_to(Building));
+    }
+}
@@ -0,0 +1,200 @@
+//! Release batching policy
+//!
+//! Decides when a branch queue has collected enough to be released. The
+//! thresholds live in `.github/release_queue/policy.json` so they can be tuned
+//! without touching the scripts; a branch is ready as soon as any configured
+//! threshold is met.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::lifecycle::EntryState;
+use super::QueueFile;
+
+/// File name of the policy inside the queue directory
+pub const POLICY_FILE: &str = "policy.json";
+
+/// Thresholds for one branch; unset thresholds never trigger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchPolicy {
+    /// Release once this many commits are queued
+    pub min_items: usize,
+    /// Release once the oldest queued commit has waited this many days
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Force a release of whatever is queued this many days after the last one,
+    /// or after the oldest queued commit while the branch has no release yet
+    #[serde(default)]
+    pub max_wait_days: Option<u32>,
+}
+
+/// Batching policy for all release branches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleasePolicy {
+    pub branches: BTreeMap<String, BranchPolicy>,
+}
+
+/// Outcome of checking a queue against its policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Commits still needed to reach `min_items`
+    pub remaining_items: usize,
+    /// Commits released together, `min_items` of the branch
+    pub batch_size: usize,
+    /// Thresholds that were met, in human-readable form
+    pub reasons: Vec<String>,
+}
+
+impl Default for ReleasePolicy {
+    fn default() -> Self {
+        let mut branches = BTreeMap::new();
+        branches.insert("beta".to_string(), BranchPolicy::default_for("beta"));
+        branches.insert("main".to_string(), BranchPolicy::default_for("main"));
+        Self { branches }
+    }
+}
+
+impl BranchPolicy {
+    /// Built-in thresholds used when the policy file does not cover a branch
+    pub fn default_for(branch: &str) -> Self {
+        match branch {
+            "main" => Self { min_items: 15, max_age_days: Some(14), max_wait_days: None },
+            _ => Self { min_items: 10, max_age_days: None, max_wait_days: None },
+        }
+    }
+
+    /// Check a queue against these thresholds at time `now`
+    ///
+    /// Failed entries wait for a retry and do not count towards a release.
+    pub fn evaluate(&self, queue: &QueueFile, now: DateTime<Utc>) -> Readiness {
+        let waiting: Vec<_> = queue.items.iter().filter(|item| item.state != EntryState::Failed).collect();
+        let count = waiting.len();
+        let mut reasons = Vec::new();
+
+        if count > 0 && count >= self.min_items {
+            reasons.push(format!("{} commits queued (minimum {})", count, self.min_items));
+        }
+
+        if let (Some(days), Some(oldest)) = (self.max_age_days, waiting.iter().map(|item| item.date).min()) {
+            let age = now - oldest;
+            if age >= Duration::days(days.into()) {
+                reasons.push(format!("oldest commit queued {} days ago (maximum {})", age.num_days(), days));
+            }
+        }
+
+        // A fresh or migrated queue has no release to wait from, so the wait
+        // starts with the oldest queued commit instead
+        if let Some(days) = self.max_wait_days {
+            match (queue.last_released_at, waiting.iter().map(|item| item.date).min()) {
+                (Some(last), _) if count > 0 && now - last >= Duration::days(days.into()) => {
+                    reasons.push(format!("last release was {} days ago (maximum wait {})", (now - last).num_days(), days));
+                },
+                (None, Some(oldest)) if now - oldest >= Duration::days(days.into()) => {
+                    reasons.push(format!("no release yet and oldest commit queued {} days ago (maximum wait {})",
+                        (now - oldest).num_days(), days));
+                },
+                _ => {},
+            }
+        }
+
+        Readiness {
+            ready: !reasons.is_empty(),
+            remaining_items: self.min_items.saturating_sub(count),
+            batch_size: self.min_items,
+            reasons,
+        }
+    }
+}
+
+impl ReleasePolicy {
+    /// Load the policy from a queue directory, falling back to the built-in defaults
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(POLICY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Thresholds for a branch
+    pub fn for_branch(&self, branch: &str) -> BranchPolicy {
+        self.branches
+            .get(branch)
+            .cloned()
+            .unwrap_or_else(|| BranchPolicy::default_for(branch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release_queue::QueueItem;
+
+    fn queue_with(count: usize, age_days: i64) -> QueueFile {
+        let mut queue = QueueFile::new("beta");
+        for i in 0..count {
+            let mut item = QueueItem::new(&format!("{:040x}", i), None, Default::default());
+            item.date = Utc::now() - Duration::days(age_days);
+            queue.items.push(item);
+        }
+        queue
+    }
+
+    #[test]
+    fn test_remaining_saturates_past_minimum() {
+        let policy = BranchPolicy { min_items: 3, max_age_days: None, max_wait_days: None };
+
+        let below = policy.evaluate(&queue_with(1, 0), Utc::now());
+        assert!(!below.ready);
+        assert_eq!(below.remaining_items, 2);
+
+        let above = policy.evaluate(&queue_with(5, 0), Utc::now());
+        assert!(above.ready);
+        assert_eq!(above.remaining_items, 0);
+    }
+
+    #[test]
+    fn test_age_and_wait_thresholds() {
+        let policy = BranchPolicy { min_items: 10, max_age_days: Some(7), max_wait_days: Some(3) };
+        assert!(policy.evaluate(&queue_with(1, 8), Utc::now()).ready);
+        assert!(!policy.evaluate(&queue_with(1, 1), Utc::now()).ready);
+
+        let mut queue = queue_with(1, 1);
+        queue.last_released_at = Some(Utc::now() - Duration::days(4));
+        let readiness = policy.evaluate(&queue, Utc::now());
+        assert!(readiness.ready);
+        assert_eq!(readiness.reasons.len(), 1);
+
+        // An empty queue is never forced out
+        queue.items.clear();
+        assert!(!policy.evaluate(&queue, Utc::now()).ready);
+    }
+
+    #[test]
+    fn test_wait_without_release_counts_from_oldest_commit() {
+        let policy = BranchPolicy { min_items: 10, max_age_days: None, max_wait_days: Some(3) };
+        assert!(!policy.evaluate(&queue_with(1, 2), Utc::now()).ready);
+
+        let readiness = policy.evaluate(&queue_with(1, 4), Utc::now());
+        assert!(readiness.ready);
+        assert!(readiness.reasons[0].starts_with("no release yet"));
+
+        assert!(!policy.evaluate(&queue_with(0, 0), Utc::now()).ready);
+    }
+
+    #[test]
+    fn test_missing_file_uses_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = ReleasePolicy::load(dir.path()).unwrap();
+        assert_eq!(policy.for_branch("main").min_items, 15);
+    }
+}
@@ -0,0 +1,212 @@
+//! Human-readable queue status
+//!
+//! A [`StatusReport`] renders the whole branch queue (position, commit, PR,
+//! age, ETA and state) for the places the workflows show it: the job summary
+//! and PR comments get Markdown, logs and commit messages get plain text.
+
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+
+use super::eta::format_duration;
+use super::policy::Readiness;
+use super::QueueFile;
+
+/// Hidden marker that identifies queue status comments on a PR
+pub const COMMENT_MARKER: &str = "<!-- release-queue-status -->";
+
+/// Snapshot of a branch queue ready to be rendered
+#[derive(Debug, Clone)]
+pub struct StatusReport {
+    pub queue: QueueFile,
+    pub readiness: Readiness,
+    /// Commit to emphasise, usually the one the workflow just queued
+    pub highlight: Option<String>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// One rendered queue row
+struct Row {
+    position: usize,
+    commit: String,
+    pr: String,
+    age: String,
+    eta: String,
+    state: String,
+    highlighted: bool,
+}
+
+impl StatusReport {
+    pub fn new(queue: QueueFile, readiness: Readiness) -> Self {
+        Self { queue, readiness, highlight: None, generated_at: Utc::now() }
+    }
+
+    /// Emphasise a commit in the rendered table
+    pub fn with_highlight(mut self, sha: &str) -> Self {
+        self.highlight = Some(sha.to_string());
+        self
+    }
+
+    /// Markdown for `GITHUB_STEP_SUMMARY`
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "## 🚀 Release Queue: `{}`", self.queue.branch);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{}", self.headline());
+
+        if let Some(position) = self.highlighted_position() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "Commit `{}` is at position **{}**.", short_sha(self.highlight.as_deref().unwrap_or_default()), position);
+        }
+
+        let rows = self.rows();
+        if rows.is_empty() {
+            return out;
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "| # | Commit | PR | Age | ETA | State |");
+        let _ = writeln!(out, "|---|--------|----|-----|-----|-------|");
+        for row in rows {
+            let cells = [
+                row.position.to_string(),
+                format!("`{}`", row.commit),
+                row.pr,
+                row.age,
+                row.eta,
+                row.state,
+            ];
+            let cells: Vec<String> = if row.highlighted {
+                cells.into_iter().map(|cell| format!("**{}**", cell)).collect()
+            } else {
+                Vec::from(cells)
+            };
+            let _ = writeln!(out, "| {} |", cells.join(" | "));
+        }
+
+        out
+    }
+
+    /// Markdown for a PR comment, tagged so later runs can find and update it
+    pub fn to_comment(&self) -> String {
+        format!("{}\n{}", COMMENT_MARKER, self.to_markdown())
+    }
+
+    /// Plain text for terminals, logs and commit messages
+    pub fn to_terminal(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Release queue: {}", self.queue.branch);
+        let _ = writeln!(out, "{}", self.headline());
+
+        for row in self.rows() {
+            let _ = writeln!(
+                out,
+                "{} {:>3}  {:<7}  {:<6}  {:>5}  {:<10}  {}",
+                if row.highlighted { '>' } else { ' ' },
+                row.position,
+                row.commit,
+                row.pr,
+                row.age,
+                row.eta,
+                row.state
+            );
+        }
+
+        out
+    }
+
+    fn headline(&self) -> String {
+        let count = self.queue.items.len();
+        let queued = format!("{} commit{} queued", count, if count == 1 { "" } else { "s" });
+
+        if self.readiness.ready {
+            format!("{}, ready to release ({}).", queued, self.readiness.reasons.join("; "))
+        } else {
+            format!("{}, {} more needed before the next release.", queued, self.readiness.remaining_items)
+        }
+    }
+
+    fn highlighted_position(&self) -> Option<usize> {
+        self.highlight.as_deref().and_then(|sha| self.queue.position(sha))
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        self.queue
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let position = index + 1;
+                let estimate = self.queue.estimate(position, self.readiness.batch_size);
+
+                Row {
+                    position,
+                    commit: short_sha(&item.commit).to_string(),
+                    pr: item.pr.map(|pr| format!("#{}", pr)).unwrap_or_else(|| "-".to_string()),
+                    age: format_age(self.generated_at - item.date),
+                    eta: if estimate.expected.is_zero() {
+                        "next".to_string()
+                    } else {
+                        format!("~{}", format_duration(estimate.expected))
+                    },
+                    state: item.state.to_string(),
+                    highlighted: self.highlight.as_deref() == Some(item.commit.as_str()),
+                }
+            })
+            .collect()
+    }
+}
+
+/// First seven characters of a commit; hand-edited entries may not be hex
+fn short_sha(sha: &str) -> &str {
+    sha.char_indices().nth(7).map_or(sha, |(end, _)| &sha[..end])
+}
+
+/// Compact age such as `45m`, `5h` or `3d`
+fn format_age(age: chrono::Duration) -> String {
+    if age.num_days() > 0 {
+        format!("{}d", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h", age.num_hours())
+    } else {
+        format!("{}m", age.num_minutes().max(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release_queue::{Priority, QueueItem};
+
+    #[test]
+    fn test_markdown_table_highlights_commit() {
+        let mut queue = QueueFile::new("beta");
+        for (commit, pr, hours) in [('a', Some(12), 50), ('b', None, 3)] {
+            let mut item = QueueItem::new(&commit.to_string().repeat(40), pr, Priority::Normal);
+            item.date = Utc::now() - chrono::Duration::hours(hours);
+            queue.items.push(item);
+        }
+        let readiness = Readiness { ready: false, remaining_items: 8, batch_size: 1, reasons: Vec::new() };
+        let report = StatusReport::new(queue, readiness).with_highlight(&"b".repeat(40));
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("2 commits queued, 8 more needed"));
+        assert!(markdown.contains("Commit `bbbbbbb` is at position **2**."));
+        assert!(markdown.contains("| 1 | `aaaaaaa` | #12 | 2d | next | pending |"));
+        assert!(markdown.contains("| **2** | **`bbbbbbb`** | **-** | **3h** | **~15 minutes** | **pending** |"));
+
+        assert!(report.to_comment().starts_with(COMMENT_MARKER));
+        let terminal = report.to_terminal();
+        assert!(terminal.contains(">   2  bbbbbbb"));
+        assert!(!terminal.contains('|'));
+    }
+
+    #[test]
+    fn test_non_ascii_commit_renders() {
+        let mut queue = QueueFile::new("beta");
+        queue.items.push(QueueItem::new("äöüäöüäöü", None, Priority::Normal));
+        let readiness = Readiness { ready: false, remaining_items: 9, batch_size: 1, reasons: Vec::new() };
+
+        let markdown = StatusReport::new(queue, readiness).to_markdown();
+        assert!(markdown.contains("`äöüäöüä`"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Verification of commit signatures against trusted signers
+//!
+//! The keys allowed to sign released commits are listed in
+//! `.github/trusted-signers.json`:
+//!
+//! ```json
+//! {
+//!   "signers": [
+//!     {
+//!       "name": "Release Bot",
+//!       "fingerprint": "0123456789ABCDEF0123456789ABCDEF01234567",
+//!       "public_key": ".github/signing-keys/release-bot.asc"
+//!     },
+//!     { "name": "Maintainer (SSH)", "fingerprint": "SHA256:..." }
+//!   ]
+//! }
+//! ```
+//!
+//! GPG signers need their armored `public_key` (relative to the repository
+//! root) for git to check the signature; a signature of a key that is not in
+//! the keyring cannot be checked and counts as untrusted. SSH signatures carry
+//! their public key, so the `SHA256:` fingerprint is enough. A commit is
+//! trusted if its signature is good and made by the primary key or a subkey
+//! of a listed signer.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::Gpg;
+
+/// Default location of the allowlist, relative to the repository root
+pub const TRUSTED_SIGNERS_FILE: &str = ".github/trusted-signers.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TrustedSigner {
+    pub name: String,
+    /// GPG fingerprint, or `SHA256:` fingerprint of an SSH key
+    pub fingerprint: String,
+    /// Armored GPG public key, relative to the repository root
+    #[serde(default)]
+    pub public_key: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TrustedSigners {
+    pub signers: Vec<TrustedSigner>,
+}
+
+impl TrustedSigners {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Signer with the fingerprint; GPG fingerprints compare case-insensitively
+    pub fn find(&self, fingerprint: &str) -> Option<&TrustedSigner> {
+        let fingerprint = fingerprint.replace(' ', "");
+        self.signers.iter().find(|signer| {
+            let listed = signer.fingerprint.replace(' ', "");
+            if listed.starts_with("SHA256:") {
+                listed == fingerprint
+            } else {
+                listed.eq_ignore_ascii_case(&fingerprint)
+            }
+        })
+    }
+
+    /// Import the signers' GPG public keys into `gpg`
+    pub fn import(&self, root: &Path, gpg: &Gpg) -> Result<()> {
+        for signer in &self.signers {
+            let Some(public_key) = &signer.public_key else {
+                continue;
+            };
+            let path = root.join(public_key);
+            let output = gpg.command()
+                .arg("--import")
+                .arg(&path)
+                .output()
+                .context("Failed to run gpg --import")?;
+            if !output.status.success() {
+                bail!("Failed to import the key of {} from {}: {}", signer.name, path.display(), String::from_utf8_lossy(&output.stderr).trim());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Good signature of a listed signer
+    Trusted { signer: String },
+    Unsigned,
+    /// Good signature of a key that is not listed, or a key not in the keyring
+    Untrusted { key: String },
+    /// Bad, expired or revoked signature
+    Invalid { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSignature {
+    pub sha: String,
+    pub author: String,
+    pub subject: String,
+    pub status: SignatureStatus,
+}
+
+impl CommitSignature {
+    pub fn is_trusted(&self) -> bool {
+        matches!(self.status, SignatureStatus::Trusted { .. })
+    }
+}
+
+/// Signatures of the commits in `from..to`, or of all commits up to `to`
+///
+/// `gpg` should hold only the trusted signers' public keys.
+pub fn verify_commits(repository: &Path, gpg: &Gpg, signers: &TrustedSigners, from: Option<&str>, to: &str) -> Result<Vec<CommitSignature>> {
+    // SSH signatures are only checked with an allowed signers file; an empty
+    // one still reports the key, as good with unknown validity
+    let allowed_signers = gpg.home().join("allowed_signers_verify");
+    fs::write(&allowed_signers, "").context("Failed to write the allowed signers file")?;
+
+    let range = match from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .arg("-c")
+        .arg(format!("gpg.ssh.allowedSignersFile={}", allowed_signers.display()))
+        .args(["log", "--format=%H%x1f%G?%x1f%GF%x1f%GP%x1f%GK%x1f%an%x1f%s%x1e", &range])
+        .env("GNUPGHOME", gpg.home())
+        .output()
+        .context("Failed to run git log")?;
+    if !output.status.success() {
+        bail!("Failed to list commits in {}: {}", range, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    Ok(log
+        .split('\x1e')
+        .map(str::trim)
+        .filter(|record| !record.is_empty())
+        .map(|record| parse_commit(record, signers))
+        .collect())
+}
+
+fn parse_commit(record: &str, signers: &TrustedSigners) -> CommitSignature {
+    let fields: Vec<&str> = record.split('\x1f').collect();
+    let field = |index: usize| fields.get(index).copied().unwrap_or_default();
+    let (code, fingerprint, primary, key) = (field(1), field(2), field(3), field(4));
+
+    let status = match code {
+        "N" => SignatureStatus::Unsigned,
+        "G" | "U" => {
+            let signer = [primary, fingerprint]
+                .into_iter()
+                .filter(|fingerprint| !fingerprint.is_empty())
+                .find_map(|fingerprint| signers.find(fingerprint));
+            match signer {
+                Some(signer) => SignatureStatus::Trusted { signer: signer.name.clone() },
+                None => SignatureStatus::Untrusted { key: if primary.is_empty() { fingerprint } else { primary }.to_string() },
+            }
+        },
+        "E" => SignatureStatus::Untrusted { key: format!("{} (not in the keyring)", key) },
+        "B" => SignatureStatus::Invalid { reason: "bad signature".to_string() },
+        "X" => SignatureStatus::Invalid { reason: "expired signature".to_string() },
+        "Y" => SignatureStatus::Invalid { reason: "signed with an expired key".to_string() },
+        "R" => SignatureStatus::Invalid { reason: "signed with a revoked key".to_string() },
+        other => SignatureStatus::Invalid { reason: format!("unknown signature status '{}'", other) },
+    };
+
+    CommitSignature {
+        sha: field(0).to_string(),
+        author: field(5).to_string(),
+        subject: field(6).to_string(),
+        status,
+    }
+}
+
+/// Markdown table of the commits that are not trusted, for `GITHUB_STEP_SUMMARY`
+pub fn to_markdown(commits: &[CommitSignature]) -> String {
+    let mut out = String::new();
+    let failed: Vec<&CommitSignature> = commits.iter().filter(|commit| !commit.is_trusted()).collect();
+    let _ = writeln!(out, "## 🔏 Commit Signatures");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{} of {} commit(s) signed by trusted keys", commits.len() - failed.len(), commits.len());
+    if failed.is_empty() {
+        return out;
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "| Commit | Author | Subject | Problem |");
+    let _ = writeln!(out, "|--------|--------|---------|---------|");
+    for commit in failed {
+        let problem = match &commit.status {
+            SignatureStatus::Unsigned => "unsigned".to_string(),
+            SignatureStatus::Untrusted { key } => format!("untrusted key `{}`", key),
+            SignatureStatus::Invalid { reason } => reason.clone(),
+            SignatureStatus::Trusted { .. } => continue,
+        };
+        let _ = writeln!(
+            out,
+            "| `{}` | {} | {} | {} |",
+            &commit.sha[..commit.sha.len().min(7)],
+            commit.author,
+            commit.subject.replace('|', "\\|"),
+            problem
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::Identity;
+    use crate::test_support::{git_command, run};
+
+    #[test]
+    fn test_classifies_commit_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let signing = Gpg::new(dir.path().join("signing"));
+        signing.prepare().unwrap();
+        let trusted = signing.generate_key(&Identity::new("Release Bot", "bot@example.com"), None).unwrap();
+        let stranger = signing.generate_key(&Identity::new("Stranger", "stranger@example.com"), None).unwrap();
+        let exported = signing.command().args(["--armor", "--export", &trusted.fingerprint]).output().unwrap();
+        fs::write(dir.path().join("bot.asc"), exported.stdout).unwrap();
+
+        let ssh_key = dir.path().join("id_ed25519");
+        Command::new("ssh-keygen").args(["-q", "-t", "ed25519", "-N", "", "-f"]).arg(&ssh_key).status().unwrap();
+        let ssh_fingerprint = String::from_utf8(Command::new("ssh-keygen").arg("-lf").arg(&ssh_key).output().unwrap().stdout).unwrap();
+        let ssh_fingerprint = ssh_fingerprint.split_whitespace().nth(1).unwrap().to_string();
+
+        let repository = dir.path().join("repo");
+        Command::new("git").arg("init").arg("-q").arg(&repository).status().unwrap();
+        let git = |args: &[&str]| run(git_command(&repository).env("GNUPGHOME", signing.home()).args(args));
+        git(&["commit", "-q", "--allow-empty", "-m", "base"]);
+        git(&["tag", "base"]);
+        git(&["commit", "-q", "--allow-empty", &format!("-S{}", trusted.fingerprint), "-m", "trusted gpg"]);
+        git(&["commit", "-q", "--allow-empty", &format!("-S{}", stranger.fingerprint), "-m", "untrusted gpg"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "unsigned"]);
+        let ssh_signing = format!("user.signingkey={}", ssh_key.display());
+        git(&["-c", "gpg.format=ssh", "-c", &ssh_signing, "commit", "-q", "--allow-empty", "-S", "-m", "trusted ssh"]);
+        signing.kill_agent();
+
+        let signers: TrustedSigners = serde_json::from_value(serde_json::json!({"signers": [
+            {"name": "Release Bot", "fingerprint": trusted.fingerprint.to_lowercase(), "public_key": "bot.asc"},
+            {"name": "Maintainer", "fingerprint": ssh_fingerprint}
+        ]}))
+        .unwrap();
+        let verifying = Gpg::new(dir.path().join("verifying"));
+        verifying.prepare().unwrap();
+        signers.import(dir.path(), &verifying).unwrap();
+
+        let commits = verify_commits(&repository, &verifying, &signers, Some("base"), "HEAD").unwrap();
+        verifying.kill_agent();
+        let statuses: Vec<(&str, &SignatureStatus)> = commits.iter().map(|commit| (commit.subject.as_str(), &commit.status)).collect();
+        assert_eq!(statuses, [
+            ("trusted ssh", &SignatureStatus::Trusted { signer: "Maintainer".to_string() }),
+            ("unsigned", &SignatureStatus::Unsigned),
+            ("untrusted gpg", &SignatureStatus::Untrusted { key: format!("{} (not in the keyring)", stranger.key_id) }),
+            ("trusted gpg", &SignatureStatus::Trusted { signer: "Release Bot".to_string() }),
+        ]);
+
+        let summary = to_markdown(&commits);
+        assert!(summary.contains("2 of 4 commit(s) signed by trusted keys"));
+        assert!(summary.contains("| unsigned |"));
+    }
+}
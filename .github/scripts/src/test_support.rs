@@ -0,0 +1,25 @@
+//! Helpers shared by the unit tests
+
+use std::path::Path;
+use std::process::Command;
+
+/// `git -C repository` committing as a fixed test identity
+pub fn git_command(repository: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repository)
+        .env("GIT_AUTHOR_NAME", "Test").env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test").env("GIT_COMMITTER_EMAIL", "test@example.com");
+    command
+}
+
+/// Run a command that must succeed, returning its trimmed stdout
+pub fn run(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", command, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Run git in `repository`, see [`git_command`]
+pub fn git(repository: &Path, args: &[&str]) -> String {
+    run(git_command(repository).args(args))
+}
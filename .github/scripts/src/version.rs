@@ -0,0 +1,497 @@
+//! Release version model shared by the release scripts
+//!
+//! Release tags look like `beta-v1.2.3` or `stable-v1.2.3`, optionally with
+//! SemVer pre-release identifiers and build metadata
+//! (`beta-v1.3.0-rc.1+build.5`). Plain `v1.2.3` and bare `1.2.3` versions
+//! parse too and carry no channel.
+//!
+//! The two ways of writing a release are the two [`Scheme`]s. The prefixed
+//! scheme keeps the channel in the prefix, the plain scheme marks beta
+//! releases with a leading `beta` pre-release identifier, so `beta-v1.2.3-4`
+//! and `v1.2.3-beta.4` are the same release. [`Version::to_scheme`] converts
+//! between them without losing information.
+//!
+//! Ordering follows SemVer 2.0.0 precedence of the plain form: numeric core
+//! first, a version with pre-release identifiers sorts before the same core
+//! without them, and identifiers compare numerically or lexically. Both
+//! schemes therefore order alike, with `beta-v1.2.3` before `beta-v1.2.3-4`
+//! before `stable-v1.2.3`. The scheme and build metadata do not affect
+//! precedence and only break ties so that `Ord` agrees with `Eq`.
+//!
+//! [`conventional`] picks the bump for a release from Conventional Commit
+//! messages.
+
+pub mod conventional;
+
+use anyhow::{anyhow, Result};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Release channel encoded in a tag prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Channel {
+    Beta,
+    Stable,
+}
+
+impl Channel {
+    /// Tag prefix for the channel, including the `v`
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Channel::Beta => "beta-v",
+            Channel::Stable => "stable-v",
+        }
+    }
+
+    /// Channel released from a branch
+    pub fn for_branch(branch: &str) -> Option<Self> {
+        match branch {
+            "beta" => Some(Channel::Beta),
+            "main" => Some(Channel::Stable),
+            _ => None,
+        }
+    }
+}
+
+/// How a release version is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    /// `beta-v1.2.3` and `stable-v1.2.3`, as tagged by the version determiner
+    Prefixed,
+    /// `v1.2.3` and `v1.2.3-beta.N`
+    Plain,
+}
+
+impl Scheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Prefixed => "prefixed",
+            Scheme::Plain => "plain",
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "prefixed" => Ok(Scheme::Prefixed),
+            "plain" => Ok(Scheme::Plain),
+            other => Err(anyhow!("Unknown version scheme '{}': expected prefixed or plain", other)),
+        }
+    }
+}
+
+/// Leading pre-release identifier that marks a plain-scheme beta release
+const BETA_IDENTIFIER: &str = "beta";
+
+/// A dot-separated pre-release identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{}", n),
+            Identifier::AlphaNumeric(s) => f.write_str(s),
+        }
+    }
+}
+
+/// A release version, optionally tied to a channel
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub channel: Option<Channel>,
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+impl Version {
+    /// A release version without pre-release or build metadata
+    pub fn new(channel: Option<Channel>, major: u64, minor: u64, patch: u64) -> Self {
+        Self { channel, major, minor, patch, pre: Vec::new(), build: Vec::new() }
+    }
+
+    /// Whether the version carries pre-release identifiers
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Whether the version releases to the beta channel, see [`Version::release_channel`]
+    pub fn is_beta(&self) -> bool {
+        self.release_channel() == Channel::Beta
+    }
+
+    /// The same version number on another channel
+    pub fn with_channel(&self, channel: Option<Channel>) -> Self {
+        Self { channel, ..self.clone() }
+    }
+
+    /// Next major release, e.g. `1.4.2` to `2.0.0`
+    pub fn bump_major(&self) -> Self {
+        Self::new(self.channel, self.major + 1, 0, 0)
+    }
+
+    /// Next minor release, e.g. `1.4.2` to `1.5.0`
+    pub fn bump_minor(&self) -> Self {
+        Self::new(self.channel, self.major, self.minor + 1, 0)
+    }
+
+    /// Next patch release, e.g. `1.4.2` to `1.4.3`
+    pub fn bump_patch(&self) -> Self {
+        Self::new(self.channel, self.major, self.minor, self.patch + 1)
+    }
+
+    /// Scheme the version is written in
+    pub fn scheme(&self) -> Scheme {
+        if self.channel.is_some() {
+            Scheme::Prefixed
+        } else {
+            Scheme::Plain
+        }
+    }
+
+    /// Channel the version releases to, read from the prefix or a leading `beta` identifier
+    pub fn release_channel(&self) -> Channel {
+        match self.channel {
+            Some(channel) => channel,
+            None if self.has_beta_identifier() => Channel::Beta,
+            None => Channel::Stable,
+        }
+    }
+
+    /// The same release written in another scheme
+    ///
+    /// Converting to the plain scheme moves a beta channel into a leading
+    /// `beta` pre-release identifier; converting back removes it again.
+    pub fn to_scheme(&self, scheme: Scheme) -> Self {
+        match (scheme, self.channel) {
+            (Scheme::Prefixed, Some(_)) | (Scheme::Plain, None) => self.clone(),
+            (Scheme::Prefixed, None) => {
+                let channel = self.release_channel();
+                let skip = usize::from(channel == Channel::Beta);
+                Self { channel: Some(channel), pre: self.pre[skip..].to_vec(), ..self.clone() }
+            },
+            (Scheme::Plain, Some(channel)) => {
+                let mut pre = self.pre.clone();
+                if channel == Channel::Beta {
+                    pre.insert(0, Identifier::AlphaNumeric(BETA_IDENTIFIER.to_string()));
+                }
+                Self { channel: None, pre, ..self.clone() }
+            },
+        }
+    }
+
+    /// The version as a tag: prefixed versions as is, plain versions with a `v`
+    pub fn to_tag(&self) -> String {
+        match self.scheme() {
+            Scheme::Prefixed => self.to_string(),
+            Scheme::Plain => format!("v{}", self),
+        }
+    }
+
+    /// Check that the version is one the release pipeline can publish
+    ///
+    /// Releases carry no build metadata; stable releases have no pre-release
+    /// identifiers and beta releases at most a build number
+    /// (`beta-v1.2.3`, `beta-v1.2.3-4`, `v1.2.3-beta`, `v1.2.3-beta.4`).
+    pub fn validate_release(&self) -> Result<()> {
+        if !self.build.is_empty() {
+            return Err(anyhow!("Release version {} must not carry build metadata", self.to_tag()));
+        }
+
+        let prefixed = self.to_scheme(Scheme::Prefixed);
+        match (prefixed.release_channel(), prefixed.pre.as_slice()) {
+            (_, []) | (Channel::Beta, [Identifier::Numeric(_)]) => Ok(()),
+            (Channel::Beta, _) => Err(anyhow!("Beta release {} may only carry a numeric build number", self.to_tag())),
+            (Channel::Stable, _) => Err(anyhow!("Stable release {} must not be a pre-release", self.to_tag())),
+        }
+    }
+
+    fn has_beta_identifier(&self) -> bool {
+        matches!(self.pre.first(), Some(Identifier::AlphaNumeric(first)) if first == BETA_IDENTIFIER)
+    }
+
+    /// The numeric core as `major.minor.patch`
+    pub fn core(&self) -> String {
+        format!("{}.{}.{}", self.major, self.minor, self.patch)
+    }
+
+    /// Compare by SemVer precedence only, ignoring channel and build metadata
+    pub fn cmp_precedence(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+
+    /// Highest version among `tags` on `channel`, with the tag it came from
+    ///
+    /// Tags that do not parse, are not valid releases or belong to another
+    /// channel are skipped.
+    pub fn latest<'a>(tags: impl IntoIterator<Item = &'a str>, channel: Channel) -> Option<(Version, &'a str)> {
+        tags.into_iter()
+            .filter_map(|tag| tag.trim().parse::<Version>().ok().map(|version| (version, tag.trim())))
+            .filter(|(version, _)| version.channel == Some(channel) && version.validate_release().is_ok())
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_scheme(Scheme::Plain)
+            .cmp_precedence(&other.to_scheme(Scheme::Plain))
+            .then_with(|| self.channel.cmp(&other.channel))
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(channel) = self.channel {
+            f.write_str(channel.prefix())?;
+        }
+        write!(f, "{}", self.core())?;
+
+        if !self.pre.is_empty() {
+            let pre: Vec<String> = self.pre.iter().map(Identifier::to_string).collect();
+            write!(f, "-{}", pre.join("."))?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| anyhow!("Invalid version '{}': {}", s, reason);
+
+        let (channel, rest) = if let Some(rest) = s.strip_prefix(Channel::Beta.prefix()) {
+            (Some(Channel::Beta), rest)
+        } else if let Some(rest) = s.strip_prefix(Channel::Stable.prefix()) {
+            (Some(Channel::Stable), rest)
+        } else {
+            (None, s.strip_prefix('v').unwrap_or(s))
+        };
+
+        let (rest, build) = match rest.split_once('+') {
+            Some((rest, build)) => (rest, Some(build)),
+            None => (rest, None),
+        };
+        let (core, pre) = match rest.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (rest, None),
+        };
+
+        let numbers: Vec<&str> = core.split('.').collect();
+        if numbers.len() != 3 {
+            return Err(invalid("expected MAJOR.MINOR.PATCH"));
+        }
+        let mut parsed = [0u64; 3];
+        for (slot, number) in parsed.iter_mut().zip(&numbers) {
+            *slot = parse_numeric(number).ok_or_else(|| invalid("version numbers must be non-negative integers without leading zeros"))?;
+        }
+
+        let pre = match pre {
+            Some(pre) => pre
+                .split('.')
+                .map(|part| parse_identifier(part).ok_or_else(|| invalid("malformed pre-release identifier")))
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        let build = match build {
+            Some(build) => build
+                .split('.')
+                .map(|part| {
+                    if is_identifier(part) {
+                        Ok(part.to_string())
+                    } else {
+                        Err(invalid("malformed build metadata"))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        let [major, minor, patch] = parsed;
+        Ok(Self { channel, major, minor, patch, pre, build })
+    }
+}
+
+/// Parse a numeric component, rejecting leading zeros as SemVer requires
+fn parse_numeric(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0')) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+fn parse_identifier(s: &str) -> Option<Identifier> {
+    if !is_identifier(s) {
+        return None;
+    }
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        return parse_numeric(s).map(Identifier::Numeric);
+    }
+    Some(Identifier::AlphaNumeric(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parses_prefixes_prerelease_and_build() {
+        let version = v("beta-v1.3.0-rc.1+build.5");
+        assert_eq!(version.channel, Some(Channel::Beta));
+        assert_eq!((version.major, version.minor, version.patch), (1, 3, 0));
+        assert_eq!(version.pre, [Identifier::AlphaNumeric("rc".into()), Identifier::Numeric(1)]);
+        assert_eq!(version.build, ["build", "5"]);
+        assert_eq!(version.to_string(), "beta-v1.3.0-rc.1+build.5");
+
+        assert_eq!(v("stable-v2.0.0").channel, Some(Channel::Stable));
+        assert_eq!(v("v1.2.3"), v("1.2.3"));
+        assert_eq!(v("v1.2.3").channel, None);
+    }
+
+    #[test]
+    fn test_rejects_malformed_versions() {
+        for bad in ["", "1.2", "1.2.3.4", "01.2.3", "1.2.-3", "1.2.3-", "1.2.3-beta..1", "1.2.3-01", "1.2.3+", "alpha-v1.2.3", "beta-1.2.3"] {
+            assert!(bad.parse::<Version>().is_err(), "{} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_precedence_follows_semver() {
+        // Example chain from the SemVer 2.0.0 specification
+        let chain = [
+            "1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta",
+            "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1", "1.1.0", "2.0.0",
+        ];
+        for pair in chain.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+
+        assert_eq!(v("1.0.0+a").cmp_precedence(&v("1.0.0+b")), Ordering::Equal);
+        assert_eq!(v("beta-v1.0.0").cmp_precedence(&v("stable-v1.0.0")), Ordering::Equal);
+        assert!(v("beta-v0.10.0") > v("beta-v0.9.9"));
+    }
+
+    #[test]
+    fn test_schemes_order_alike() {
+        let prefixed = ["beta-v1.2.3", "beta-v1.2.3-2", "beta-v1.2.3-11", "stable-v1.2.3", "beta-v1.2.4"];
+        for pair in prefixed.windows(2) {
+            let (a, b) = (v(pair[0]), v(pair[1]));
+            assert!(a < b, "{} < {}", a, b);
+            assert!(a.to_scheme(Scheme::Plain) < b.to_scheme(Scheme::Plain), "plain form of {} < {}", a, b);
+            assert!(a < b.to_scheme(Scheme::Plain) && a.to_scheme(Scheme::Plain) < b, "mixed forms of {} < {}", a, b);
+        }
+
+        // The same release in both schemes ties on precedence but stays distinct
+        let (prefixed, plain) = (v("beta-v1.2.3-4"), v("v1.2.3-beta.4"));
+        assert_ne!(prefixed.cmp(&plain), Ordering::Equal);
+        assert!(prefixed > v("v1.2.3-beta.3") && prefixed < v("v1.2.3-beta.5"));
+
+        assert!(!v("v1.2.3-rc.1").is_beta());
+        assert!(v("beta-v1.2.3").is_beta());
+    }
+
+    #[test]
+    fn test_scheme_matrix() {
+        // input, prefixed form, plain form, beta
+        let matrix = [
+            // Version determiner output and initial versions
+            ("beta-v0.0.1", "beta-v0.0.1", "v0.0.1-beta", true),
+            ("beta-v1.2.3", "beta-v1.2.3", "v1.2.3-beta", true),
+            ("stable-v1.2.3", "stable-v1.2.3", "v1.2.3", false),
+            // Validator and determine-version inputs
+            ("v0.0.1", "stable-v0.0.1", "v0.0.1", false),
+            ("1.2.3", "stable-v1.2.3", "v1.2.3", false),
+            ("v1.2.3-beta.4", "beta-v1.2.3-4", "v1.2.3-beta.4", true),
+            ("v1.2.3-beta", "beta-v1.2.3", "v1.2.3-beta", true),
+            ("beta-v1.2.3-4", "beta-v1.2.3-4", "v1.2.3-beta.4", true),
+        ];
+
+        for (input, prefixed, plain, beta) in matrix {
+            let version = v(input);
+            assert_eq!(version.to_scheme(Scheme::Prefixed).to_tag(), prefixed, "prefixed form of {}", input);
+            assert_eq!(version.to_scheme(Scheme::Plain).to_tag(), plain, "plain form of {}", input);
+            assert_eq!(version.release_channel() == Channel::Beta, beta, "channel of {}", input);
+            assert_eq!(version.is_beta(), beta, "is_beta of {}", input);
+            assert!(version.validate_release().is_ok(), "{} should be a valid release", input);
+
+            // Both forms are the same release and convert back losslessly
+            assert_eq!(v(prefixed).to_scheme(Scheme::Plain), v(plain));
+            assert_eq!(v(plain).to_scheme(Scheme::Prefixed), v(prefixed));
+        }
+
+        for bad in ["v1.2.3+build.1", "stable-v1.2.3-rc.1", "v1.2.3-rc.1", "beta-v1.2.3-rc.1", "v1.2.3-beta.rc"] {
+            assert!(v(bad).validate_release().is_err(), "{} should not be a valid release", bad);
+        }
+    }
+
+    #[test]
+    fn test_latest_skips_other_channels_and_garbage() {
+        let tags = ["beta-v0.9.0", "beta-v0.10.0", "stable-v9.0.0", "beta-vnext", "beta-v0.10.0-rc.1"];
+        let (version, tag) = Version::latest(tags, Channel::Beta).unwrap();
+        assert_eq!(tag, "beta-v0.10.0");
+        assert_eq!(version.bump_patch().to_string(), "beta-v0.10.1");
+        assert_eq!(version.bump_minor().to_string(), "beta-v0.11.0");
+        assert_eq!(version.bump_major().with_channel(Some(Channel::Stable)).to_string(), "stable-v1.0.0");
+        assert!(Version::latest(tags, Channel::Stable).is_some());
+        assert!(Version::latest(["v1.0.0"], Channel::Beta).is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! Conventional Commit driven version bumps
+//!
+//! Commit headers follow `type(scope)!: description`. A `!` after the type or
+//! a `BREAKING CHANGE:` footer asks for a major bump, `feat` for a minor bump
+//! and `fix` for a patch bump. Other types (`docs`, `chore`, ...) and commits
+//! that are not conventional at all do not influence the bump; when nothing
+//! does, the release is a patch.
+
+use std::fmt;
+use std::fmt::Write as _;
+
+use super::Version;
+
+/// Which version component a release increments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+
+    /// Apply the bump to a version, keeping its channel
+    pub fn apply(&self, version: &Version) -> Version {
+        match self {
+            Bump::Patch => version.bump_patch(),
+            Bump::Minor => version.bump_minor(),
+            Bump::Major => version.bump_major(),
+        }
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed Conventional Commit message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ConventionalCommit {
+    /// Parse a full commit message; `None` if the header is not conventional
+    pub fn parse(message: &str) -> Option<Self> {
+        let mut lines = message.lines();
+        let header = lines.next()?.trim();
+        let (prefix, description) = header.split_once(':')?;
+        let description = description.trim();
+        if description.is_empty() {
+            return None;
+        }
+
+        let (prefix, bang) = match prefix.strip_suffix('!') {
+            Some(prefix) => (prefix, true),
+            None => (prefix, false),
+        };
+        let (kind, scope) = match prefix.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.trim())),
+            None => (prefix, None),
+        };
+        if kind.is_empty() || !kind.bytes().all(|b| b.is_ascii_alphabetic()) {
+            return None;
+        }
+        if scope.is_some_and(str::is_empty) {
+            return None;
+        }
+
+        let footer_breaking = lines.any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"));
+
+        Some(Self {
+            kind: kind.to_lowercase(),
+            scope: scope.map(str::to_string),
+            breaking: bang || footer_breaking,
+            description: description.to_string(),
+        })
+    }
+
+    /// Bump this commit asks for, if any
+    pub fn bump(&self) -> Option<Bump> {
+        if self.breaking {
+            Some(Bump::Major)
+        } else if self.kind == "feat" {
+            Some(Bump::Minor)
+        } else if self.kind == "fix" {
+            Some(Bump::Patch)
+        } else {
+            None
+        }
+    }
+}
+
+/// A commit that asked for a bump
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BumpReason {
+    pub sha: String,
+    pub header: String,
+    pub bump: Bump,
+}
+
+/// The bump chosen for a range of commits and the commits behind it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BumpDecision {
+    pub bump: Bump,
+    /// Commits that asked for the chosen bump, oldest first
+    pub reasons: Vec<BumpReason>,
+    /// Number of commits that were considered
+    pub scanned: usize,
+}
+
+impl BumpDecision {
+    /// Decide the bump for `(sha, message)` pairs
+    pub fn from_commits<'a>(commits: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut scanned = 0;
+        let mut reasons = Vec::new();
+
+        for (sha, message) in commits {
+            scanned += 1;
+            let Some(bump) = ConventionalCommit::parse(message).and_then(|commit| commit.bump()) else {
+                continue;
+            };
+            reasons.push(BumpReason {
+                sha: sha.to_string(),
+                header: message.lines().next().unwrap_or_default().trim().to_string(),
+                bump,
+            });
+        }
+
+        let bump = reasons.iter().map(|reason| reason.bump).max().unwrap_or(Bump::Patch);
+        reasons.retain(|reason| reason.bump == bump);
+        Self { bump, reasons, scanned }
+    }
+
+    /// Plain text explanation, one driving commit per line
+    pub fn reasoning(&self) -> String {
+        let mut out = String::new();
+        if self.reasons.is_empty() {
+            let _ = writeln!(out, "{} bump: none of the {} commits asked for a feature or breaking change", self.bump, self.scanned);
+            return out;
+        }
+
+        let _ = writeln!(out, "{} bump driven by {} of {} commits:", self.bump, self.reasons.len(), self.scanned);
+        for reason in &self.reasons {
+            let _ = writeln!(out, "- {} {}", &reason.sha[..reason.sha.len().min(7)], reason.header);
+        }
+        out
+    }
+
+    /// Markdown for `GITHUB_STEP_SUMMARY`
+    pub fn to_markdown(&self, from: &str, next: &Version) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "## 🏷️ Version `{}`", next);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "**{}** bump from `{}` after scanning {} commit{}.", self.bump, from, self.scanned, if self.scanned == 1 { "" } else { "s" });
+
+        if self.reasons.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "No commit asked for a feature or breaking change.");
+            return out;
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "| Commit | Header |");
+        let _ = writeln!(out, "|--------|--------|");
+        for reason in &self.reasons {
+            let _ = writeln!(out, "| `{}` | {} |", &reason.sha[..reason.sha.len().min(7)], reason.header.replace('|', "\\|"));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_headers_and_footers() {
+        let commit = ConventionalCommit::parse("feat(queue): add prune mode\n\nDetails").unwrap();
+        assert_eq!(commit.kind, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("queue"));
+        assert_eq!(commit.bump(), Some(Bump::Minor));
+
+        assert_eq!(ConventionalCommit::parse("fix!: drop v1 tags").unwrap().bump(), Some(Bump::Major));
+        assert_eq!(
+            ConventionalCommit::parse("refactor: split module\n\nBREAKING CHANGE: renamed outputs").unwrap().bump(),
+            Some(Bump::Major)
+        );
+        assert_eq!(ConventionalCommit::parse("docs: typo").unwrap().bump(), None);
+
+        for bad in ["Merge branch 'beta'", "feat:", "feat(): empty scope", "feat(queue: unclosed", "fix bug: in parser"] {
+            assert!(ConventionalCommit::parse(bad).is_none(), "{} should not parse", bad);
+        }
+    }
+
+    #[test]
+    fn test_decision_keeps_commits_behind_highest_bump() {
+        let commits = [
+            ("a".repeat(40), "fix: handle empty queue"),
+            ("b".repeat(40), "feat: add status comment"),
+            ("c".repeat(40), "chore: bump deps"),
+            ("d".repeat(40), "feat(cli): add move"),
+        ];
+        let decision = BumpDecision::from_commits(commits.iter().map(|(sha, message)| (sha.as_str(), *message)));
+        assert_eq!(decision.bump, Bump::Minor);
+        assert_eq!(decision.scanned, 4);
+        assert_eq!(decision.reasons.len(), 2);
+        assert!(decision.reasoning().contains("- bbbbbbb feat: add status comment"));
+        assert_eq!(decision.bump.apply(&"beta-v1.4.2".parse().unwrap()).to_string(), "beta-v1.5.0");
+
+        let quiet = BumpDecision::from_commits([("e", "chore: tidy"), ("f", "Merge pull request #4")]);
+        assert_eq!(quiet.bump, Bump::Patch);
+        assert!(quiet.reasons.is_empty());
+    }
+}
@@ -21,6 +21,9 @@ handle_dockerhub_distribution() {
     fi
 }
 
+# Manifest of the latest stable release, published as a release asset
+RELEASE_MANIFEST_URL="${RELEASE_MANIFEST_URL:-https://github.com/BA-CalderonMorales/dev-environment/releases/latest/download/release-manifest.json}"
+
 # Function to handle Direct Download distribution
 handle_direct_download_distribution() {
     echo "Downloading via Direct Download..."
@@ -30,34 +33,48 @@ handle_direct_download_distribution() {
         echo "Direct Download distribution forced to fail"
         return 1
     fi
+
+    if ! command -v jq &> /dev/null; then
+        echo "Direct Download needs jq to read the release manifest"
+        return 1
+    fi
     
-    # Get latest download URL and checksum
-    DOWNLOAD_URL=$(curl -s https://raw.githubusercontent.com/BA-CalderonMorales/dev-environment/main/distributions/direct/url.txt)
-    EXPECTED_CHECKSUM=$(curl -s https://raw.githubusercontent.com/BA-CalderonMorales/dev-environment/main/distributions/direct/checksum.txt)
+    # Get the download URL and checksum from the release manifest
+    MANIFEST=$(curl -fsSL "$RELEASE_MANIFEST_URL")
+    if [ -z "$MANIFEST" ]; then
+        echo "Failed to fetch the release manifest"
+        return 1
+    fi
+    RELEASE_VERSION=$(echo "$MANIFEST" | jq -r '.version // empty')
+    DOWNLOAD_URL=$(echo "$MANIFEST" | jq -r '.assets[] | select(.name == "dev-environment.tar.gz") | .url')
+    EXPECTED_CHECKSUM=$(echo "$MANIFEST" | jq -r '.assets[] | select(.name == "dev-environment.tar.gz") | .sha256')
     
     if [ -z "$DOWNLOAD_URL" ] || [ -z "$EXPECTED_CHECKSUM" ]; then
-        echo "Failed to fetch download URL or checksum"
+        echo "Release manifest ${RELEASE_VERSION} lists no dev-environment.tar.gz"
         return 1
     fi
     
-    echo "Starting download..."
-    curl -L -o dev-environment.tar "$DOWNLOAD_URL"
+    echo "Starting download of ${RELEASE_VERSION}..."
+    curl -fL -o dev-environment.tar.gz "$DOWNLOAD_URL"
     
-    if [ -f "dev-environment.tar" ]; then
+    if [ -f "dev-environment.tar.gz" ]; then
         # Verify checksum
-        ACTUAL_CHECKSUM=$(sha256sum dev-environment.tar | cut -d' ' -f1)
+        ACTUAL_CHECKSUM=$(sha256sum dev-environment.tar.gz | cut -d' ' -f1)
         if [ "$ACTUAL_CHECKSUM" != "$EXPECTED_CHECKSUM" ]; then
             echo "Checksum verification failed"
-            rm dev-environment.tar
+            rm dev-environment.tar.gz
             return 1
         fi
         
-        echo "Loading Docker image..."
-        docker load < dev-environment.tar
-        rm dev-environment.tar
+        # The archive holds the compose file and Dockerfiles of the release
+        echo "Extracting release files..."
+        RELEASE_DIR="${DEV_ENVIRONMENT_DIR:-dev-environment}"
+        mkdir -p "$RELEASE_DIR"
+        tar -xzf dev-environment.tar.gz -C "$RELEASE_DIR"
+        rm dev-environment.tar.gz
         echo "Starting environment..."
-        docker compose up -d
-        return 0
+        (cd "$RELEASE_DIR" && docker compose up -d)
+        return $?
     else
         echo "Download failed."
         return 1